@@ -0,0 +1,83 @@
+// The result of one part of a day's puzzle.
+//
+// Each part picks whichever variant suits it, so a day can answer part one
+// with a small count and part two with something huge, negative or textual.
+
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Answer {
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    I64(i64),
+    I128(i128),
+    Str(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::U32(n) => n.fmt(f),
+            Answer::U64(n) => n.fmt(f),
+            Answer::U128(n) => n.fmt(f),
+            Answer::I64(n) => n.fmt(f),
+            Answer::I128(n) => n.fmt(f),
+            Answer::Str(s) => s.fmt(f),
+        }
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(n: u32) -> Self { Answer::U32(n) }
+}
+
+impl From<u64> for Answer {
+    fn from(n: u64) -> Self { Answer::U64(n) }
+}
+
+impl From<u128> for Answer {
+    fn from(n: u128) -> Self { Answer::U128(n) }
+}
+
+impl From<i64> for Answer {
+    fn from(n: i64) -> Self { Answer::I64(n) }
+}
+
+impl From<i128> for Answer {
+    fn from(n: i128) -> Self { Answer::I128(n) }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self { Answer::Str(s) }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self { Answer::Str(s.to_string()) }
+}
+
+#[cfg(test)]
+mod display_tests {
+    use super::*;
+
+    #[test]
+    fn negative_numbers() {
+        assert_eq!("-42", Answer::I64(-42).to_string());
+        assert_eq!("-170141183460469231731687303715884105728", Answer::I128(i128::MIN).to_string());
+    }
+
+    #[test]
+    fn big_numbers() {
+        assert_eq!("340282366920938463463374607431768211455", Answer::from(u128::MAX).to_string());
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!("EFJKZLBL", Answer::from("EFJKZLBL").to_string());
+    }
+
+    #[test]
+    fn respects_padding() {
+        assert_eq!("   17", format!("{:>5}", Answer::U32(17)));
+    }
+}
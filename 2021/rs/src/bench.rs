@@ -0,0 +1,156 @@
+// A tiny std-only benchmark harness for comparing solution approaches.
+//
+// Parsing and each part are timed separately, since a clever parse can make
+// both parts look faster than they really are (or the other way around).
+
+use crate::Part;
+use crate::error::Result;
+use crate::input::Input;
+use crate::solver::{selected_parts, Solver};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BenchSettings {
+    pub warmup: usize,
+    pub samples: usize,
+}
+
+impl Default for BenchSettings {
+    fn default() -> Self {
+        BenchSettings {
+            warmup: 5,
+            samples: 50,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        assert!(!samples.is_empty(), "can't summarise zero samples");
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let mid = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) {
+            (sorted[mid - 1] + sorted[mid]) / 2
+        } else {
+            sorted[mid]
+        };
+
+        let secs: Vec<f64> = sorted.iter().map(Duration::as_secs_f64).collect();
+        let mean = secs.iter().sum::<f64>() / secs.len() as f64;
+        let variance = secs.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / secs.len() as f64;
+
+        Stats {
+            min: sorted[0],
+            median,
+            mean: Duration::from_secs_f64(mean),
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        }
+    }
+}
+
+// Timings for each stage that was benchmarked, labelled for the report.
+pub type Timings = Vec<(&'static str, Stats)>;
+
+// Run `f` for the warm-up rounds and then the measured ones. `setup` isn't
+// timed, so anything it has to prepare stays out of the numbers.
+fn measure<T, R>(settings: &BenchSettings, mut setup: impl FnMut() -> T, mut f: impl FnMut(T) -> R) -> Stats {
+    for _ in 0..settings.warmup {
+        black_box(f(setup()));
+    }
+
+    let samples: Vec<Duration> = (0..settings.samples.max(1))
+        .map(|_| {
+            let arg = setup();
+            let start = Instant::now();
+            black_box(f(arg));
+            start.elapsed()
+        })
+        .collect();
+
+    Stats::from_samples(&samples)
+}
+
+pub fn run<S: Solver>(input: &Input, part: Option<Part>, settings: &BenchSettings) -> Result<Timings> {
+    // Make sure everything actually works before spending time measuring it
+    let parsed = S::parse(input)?;
+    let parts = selected_parts(part);
+    for part in &parts {
+        match part {
+            Part::One => S::part1(&parsed)?,
+            Part::Two => S::part2(&parsed)?,
+        };
+    }
+
+    let mut timings = vec![
+        ("parse", measure(settings, || input, S::parse)),
+    ];
+    for part in parts {
+        let stats = match part {
+            Part::One => measure(settings, || &parsed, S::part1),
+            Part::Two => measure(settings, || &parsed, S::part2),
+        };
+        timings.push((part.label(), stats));
+    }
+
+    Ok(timings)
+}
+
+pub fn report(timings: &Timings) -> String {
+    let mut lines = vec![
+        format!("{:<8}{:>12}{:>12}{:>12}{:>12}", "", "min", "median", "mean", "stddev"),
+    ];
+    for (label, stats) in timings {
+        lines.push(format!("{:<8}{:>12}{:>12}{:>12}{:>12}", label,
+            format!("{:.1?}", stats.min),
+            format!("{:.1?}", stats.median),
+            format!("{:.1?}", stats.mean),
+            format!("{:.1?}", stats.stddev)));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use super::*;
+
+    fn micros(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|&v| Duration::from_micros(v)).collect()
+    }
+
+    #[test]
+    fn odd_sample_count() {
+        let stats = Stats::from_samples(&micros(&[5, 1, 3]));
+        assert_eq!(Duration::from_micros(1), stats.min);
+        assert_eq!(Duration::from_micros(3), stats.median);
+        assert_eq!(Duration::from_micros(3), stats.mean);
+    }
+
+    #[test]
+    fn even_sample_count() {
+        let stats = Stats::from_samples(&micros(&[2, 4, 4, 4, 5, 5, 7, 9]));
+        assert_eq!(Duration::from_micros(4) + Duration::from_nanos(500), stats.median);
+        assert_eq!(Duration::from_micros(5), stats.mean);
+        assert_eq!(Duration::from_micros(2), stats.stddev);
+    }
+
+    #[test]
+    fn times_every_stage() {
+        let input = crate::read_input("../testinputs/01.txt");
+        let settings = BenchSettings { warmup: 1, samples: 3 };
+        let timings = run::<crate::day_01::DepthMeasurements>(&input, None, &settings).unwrap();
+        let labels: Vec<&str> = timings.iter().map(|(label, _)| *label).collect();
+        assert_eq!(vec!["parse", "part 1", "part 2"], labels);
+    }
+}
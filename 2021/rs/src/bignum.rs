@@ -0,0 +1,137 @@
+// Unsigned integers as big as they need to be, for the answers that outgrow
+// u128. Only addition and multiplication so far, since that's all anything
+// has needed.
+
+use std::fmt;
+use std::ops::{Add, Mul};
+
+// Little-endian base 2^32 digits, with no zeros on the end, so zero is empty.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    fn trim(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    // Divide in place by a single digit, handing back the remainder.
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let current = (remainder << 32) | u64::from(*limb);
+            *limb = (current / u64::from(divisor)) as u32;
+            remainder = current % u64::from(divisor);
+        }
+        *self = std::mem::take(self).trim();
+        remainder as u32
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> Self {
+        BigUint { limbs: vec![n as u32, (n >> 32) as u32] }.trim()
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(n: u128) -> Self {
+        let limbs = (0..4).map(|i| (n >> (32 * i)) as u32).collect();
+        BigUint { limbs }.trim()
+    }
+}
+
+impl Add for BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() { (self, other) } else { (other, self) };
+        let mut limbs = Vec::with_capacity(long.limbs.len() + 1);
+        let mut carry = 0u64;
+        for (i, &limb) in long.limbs.iter().enumerate() {
+            let sum = u64::from(limb) + u64::from(short.limbs.get(i).copied().unwrap_or(0)) + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+        BigUint { limbs }
+    }
+}
+
+// Schoolbook long multiplication, which is plenty for the sizes we see.
+impl Mul for BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::default();
+        }
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let product = u64::from(a) * u64::from(b) + u64::from(limbs[i + j]) + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint { limbs }.trim()
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return f.pad("0");
+        }
+        // Peel off nine decimal digits at a time, least significant first.
+        let mut n = self.clone();
+        let mut chunks = Vec::new();
+        while !n.is_zero() {
+            chunks.push(n.div_rem_small(1_000_000_000));
+        }
+        let mut digits = chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:09}", chunk));
+        }
+        f.pad(&digits)
+    }
+}
+
+#[cfg(test)]
+mod bignum_tests {
+    use super::*;
+
+    #[test]
+    fn matches_native_arithmetic() {
+        let a = 0xdead_beef_cafe_u64;
+        let b = 0x1234_5678_9abc_u64;
+        assert_eq!(BigUint::from(u128::from(a) * u128::from(b)), BigUint::from(a) * BigUint::from(b));
+        assert_eq!(BigUint::from(u128::from(u64::MAX) + 1), BigUint::from(u64::MAX) + BigUint::from(1u64));
+        assert_eq!(BigUint::default(), BigUint::from(0u64) * BigUint::from(5u64));
+    }
+
+    #[test]
+    fn decimal_display() {
+        assert_eq!("0", BigUint::default().to_string());
+        assert_eq!(u128::MAX.to_string(), BigUint::from(u128::MAX).to_string());
+        assert_eq!("1000000000", BigUint::from(1_000_000_000u64).to_string());
+        // 2^128 squared
+        let big = BigUint::from(u128::MAX) + BigUint::from(1u64);
+        assert_eq!(
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936",
+            (big.clone() * big).to_string()
+        );
+    }
+}
@@ -0,0 +1,263 @@
+// Command-line handling for the runner.
+//
+// Hand-rolled on purpose: the whole crate is std-only, and the grammar is
+// small enough that a flag parser would be more code to configure than this.
+
+use crate::Part;
+use crate::bench::BenchSettings;
+
+pub const USAGE: &str = "\
+Usage:
+    aoc-2021                      Run every day against its puzzle input
+    aoc-2021 run [DAY] [OPTIONS]  Run one day (or every day if DAY is omitted)
+    aoc-2021 bench [DAY] [OPTIONS]
+                                  Time parsing and each part of one day (or every day)
+    aoc-2021 verify [DAY] [OPTIONS] [--record]
+                                  Check answers against the known ones in ../answers.txt,
+                                  recording any unknown ones with --record
+    aoc-2021 render [DAY] [OPTIONS] [--out DIR]
+                                  Draw the input of days that know how as netpbm images
+                                  (or CSV tables and text diagrams) in DIR (default .)
+    aoc-2021 new-day DAY [NAME]   Start a day from src/day_tmp.rs, naming its solver
+                                  after NAME (e.g. smoke_basin), and add empty inputs
+    aoc-2021 help                 Show this message
+
+Options for run:
+    --part <1|2>      Only print the answer for one part
+    --input <PATH>    Read the puzzle input from PATH instead of ../inputs/NN.txt
+    --example         Read the example input from ../testinputs/NN.txt
+
+Run options also apply to bench, verify and render. Bench additionally takes:
+    --warmup <N>      Untimed runs before measuring (default 5)
+    --samples <N>     Timed runs to summarise (default 50)";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Run(RunOptions),
+    Bench(RunOptions, BenchSettings),
+    Verify(RunOptions, bool),
+    Render(RunOptions, String),
+    NewDay(u8, Option<String>),
+    Help,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RunOptions {
+    pub day: Option<u8>,
+    pub part: Option<Part>,
+    pub input: Option<String>,
+    pub example: bool,
+}
+
+impl RunOptions {
+    // Where the input for `day` should be read from with these options.
+    pub fn input_path(&self, day: u8) -> String {
+        match &self.input {
+            Some(path) => path.clone(),
+            None if self.example => format!("../testinputs/{:02}.txt", day),
+            None => format!("../inputs/{:02}.txt", day),
+        }
+    }
+}
+
+// Parse everything after the program name.
+pub fn parse<I>(args: I) -> Result<Command, String>
+    where I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+
+    match args.next().as_deref() {
+        None => Ok(Command::Run(RunOptions::default())),
+        Some("run") => Ok(Command::Run(parse_run(args, None)?)),
+        Some("bench") => {
+            let mut settings = BenchSettings::default();
+            let opts = parse_run(args, Some(&mut settings))?;
+            Ok(Command::Bench(opts, settings))
+        },
+        Some("verify") => {
+            let mut args: Vec<String> = args.collect();
+            let before = args.len();
+            args.retain(|arg| arg != "--record");
+            let record = args.len() != before;
+            Ok(Command::Verify(parse_run(args.into_iter(), None)?, record))
+        },
+        Some("render") => {
+            let mut args: Vec<String> = args.collect();
+            let mut out_dir = ".".to_string();
+            if let Some(at) = args.iter().position(|arg| arg == "--out") {
+                if at + 1 == args.len() {
+                    return Err("--out needs a directory".into());
+                }
+                out_dir = args.remove(at + 1);
+                args.remove(at);
+            }
+            Ok(Command::Render(parse_run(args.into_iter(), None)?, out_dir))
+        },
+        Some("new-day") => {
+            let day = parse_day(&args.next().ok_or("new-day needs a DAY")?)?;
+            let name = args.next();
+            if let Some(extra) = args.next() {
+                return Err(format!("unexpected argument '{}'", extra));
+            }
+            Ok(Command::NewDay(day, name))
+        },
+        Some("help" | "-h" | "--help") => Ok(Command::Help),
+        Some(other) => Err(format!("unknown command '{}'", other)),
+    }
+}
+
+// The options shared by run and bench, plus bench's own when given `settings`.
+fn parse_run<I>(mut args: I, mut settings: Option<&mut BenchSettings>) -> Result<RunOptions, String>
+    where I: Iterator<Item = String>,
+{
+    let mut opts = RunOptions::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--part" => {
+                let value = args.next().ok_or("--part needs a value")?;
+                opts.part = Some(parse_part(&value)?);
+            },
+            "--input" => {
+                let value = args.next().ok_or("--input needs a path")?;
+                opts.input = Some(value);
+            },
+            "--example" => opts.example = true,
+            "--warmup" | "--samples" if settings.is_some() => {
+                let value = args.next().ok_or_else(|| format!("{} needs a count", arg))?;
+                let count = value.parse::<usize>()
+                    .map_err(|_| format!("'{}' is not a count", value))?;
+                let settings = settings.as_deref_mut().unwrap();
+                if arg == "--warmup" {
+                    settings.warmup = count;
+                } else if count == 0 {
+                    return Err("--samples needs to be at least 1".into());
+                } else {
+                    settings.samples = count;
+                }
+            },
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown option '{}'", flag));
+            },
+            day if opts.day.is_none() => opts.day = Some(parse_day(day)?),
+            extra => return Err(format!("unexpected argument '{}'", extra)),
+        }
+    }
+
+    if opts.input.is_some() && opts.example {
+        return Err("--input and --example can't be used together".into());
+    }
+    if opts.input.is_some() && opts.day.is_none() {
+        return Err("--input needs a DAY to run it against".into());
+    }
+
+    Ok(opts)
+}
+
+pub fn parse_day(s: &str) -> Result<u8, String> {
+    match s.parse::<u8>() {
+        Ok(day) if (1..=25).contains(&day) => Ok(day),
+        _ => Err(format!("'{}' is not a day between 1 and 25", s)),
+    }
+}
+
+fn parse_part(s: &str) -> Result<Part, String> {
+    match s {
+        "1" => Ok(Part::One),
+        "2" => Ok(Part::Two),
+        _ => Err(format!("'{}' is not a part (expected 1 or 2)", s)),
+    }
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn no_arguments_runs_everything() {
+        assert_eq!(Ok(Command::Run(RunOptions::default())), parse(args("")));
+    }
+
+    #[test]
+    fn run_one_part_of_one_day() {
+        let expected = RunOptions {
+            day: Some(7),
+            part: Some(Part::Two),
+            ..Default::default()
+        };
+        assert_eq!(Ok(Command::Run(expected)), parse(args("run 07 --part 2")));
+    }
+
+    #[test]
+    fn run_with_custom_input() {
+        let Ok(Command::Run(opts)) = parse(args("run 5 --input /tmp/big.txt")) else {
+            panic!("expected a run command");
+        };
+        assert_eq!("/tmp/big.txt", opts.input_path(5));
+    }
+
+    #[test]
+    fn run_with_example_input() {
+        let Ok(Command::Run(opts)) = parse(args("run --example 3")) else {
+            panic!("expected a run command");
+        };
+        assert_eq!("../testinputs/03.txt", opts.input_path(3));
+    }
+
+    #[test]
+    fn bench_settings() {
+        let expected_settings = BenchSettings { warmup: 2, samples: 10 };
+        let expected_opts = RunOptions { day: Some(6), ..Default::default() };
+        assert_eq!(Ok(Command::Bench(expected_opts, expected_settings)),
+            parse(args("bench 6 --samples 10 --warmup 2")));
+    }
+
+    #[test]
+    fn bench_everything_by_default() {
+        assert_eq!(Ok(Command::Bench(RunOptions::default(), BenchSettings::default())),
+            parse(args("bench")));
+    }
+
+    #[test]
+    fn verify_and_record() {
+        let expected = RunOptions { day: Some(4), ..Default::default() };
+        assert_eq!(Ok(Command::Verify(expected, true)), parse(args("verify --record 4")));
+        assert_eq!(Ok(Command::Verify(RunOptions::default(), false)), parse(args("verify")));
+    }
+
+    #[test]
+    fn render_into_a_directory() {
+        let expected = RunOptions { day: Some(9), example: true, ..Default::default() };
+        assert_eq!(Ok(Command::Render(expected, "/tmp/pics".to_string())),
+            parse(args("render 9 --out /tmp/pics --example")));
+        assert_eq!(Ok(Command::Render(RunOptions::default(), ".".to_string())), parse(args("render")));
+        assert!(parse(args("render 9 --out")).is_err());
+    }
+
+    #[test]
+    fn new_day() {
+        assert_eq!(Ok(Command::NewDay(10, Some("syntax_scoring".to_string()))),
+            parse(args("new-day 10 syntax_scoring")));
+        assert_eq!(Ok(Command::NewDay(11, None)), parse(args("new-day 11")));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse(args("run 26")).is_err());
+        assert!(parse(args("run 1 --part 3")).is_err());
+        assert!(parse(args("run 1 2")).is_err());
+        assert!(parse(args("run --input foo.txt")).is_err());
+        assert!(parse(args("run 1 --input foo.txt --example")).is_err());
+        assert!(parse(args("jump")).is_err());
+        assert!(parse(args("run 1 --samples 3")).is_err());
+        assert!(parse(args("run 1 --record")).is_err());
+        assert!(parse(args("new-day")).is_err());
+        assert!(parse(args("new-day 10 a b")).is_err());
+        assert!(parse(args("bench 1 --samples 0")).is_err());
+        assert!(parse(args("bench 1 --warmup lots")).is_err());
+    }
+}
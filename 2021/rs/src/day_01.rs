@@ -1,66 +1,71 @@
 // Day one
 
 use crate::Answer;
+use crate::error::Result;
+use crate::input::Input;
+use crate::solver::Solver;
 
-pub fn depth_measurements(input_lines: Vec<String>) -> Answer {
-    const DEBUG: bool = false;
+pub struct DepthMeasurements;
 
-    let measurements: Vec<u32> = input_lines.iter()
-        .map(|x| x.parse::<u32>().unwrap()).collect();
+impl Solver for DepthMeasurements {
+    type Parsed = Vec<u32>;
 
-    fn count_increases(measurements: &[u32]) -> u32 {
-        let mut last_measurement: Option<u32> = None;
-        let mut num_increases: u32 = 0;
+    fn parse(input: &Input) -> Result<Self::Parsed> {
+        input.lines()
+            .map(|line| line.value("a depth measurement"))
+            .collect()
+    }
 
-        for measurement in measurements {
-            if let Some(last) = last_measurement {
-                if *measurement > last {
-                    num_increases += 1;
-                }
-            }
-            last_measurement = Some(*measurement);
-        }
-        num_increases
+    fn part1(measurements: &Self::Parsed) -> Result<Answer> {
+        Ok(Answer::U32(count_increases(measurements)))
     }
 
-    let increases = count_increases(&measurements);
-    if DEBUG { println!("There were {} increases", increases); }
+    fn part2(measurements: &Self::Parsed) -> Result<Answer> {
+        const WINDOW_SIZE: usize = 3;
 
-    const WINDOW_SIZE: usize = 3;
-    let mut windowed_measurements: Vec<u32> = Vec::new();
+        // Fewer measurements than one window just means no windowed increases.
+        let windowed_measurements: Vec<u32> = measurements.windows(WINDOW_SIZE)
+            .map(|window| window.iter().sum::<u32>())
+            .collect();
 
-    for i in 0..=measurements.len()-WINDOW_SIZE {
+        Ok(Answer::U32(count_increases(&windowed_measurements)))
+    }
+}
 
-        let window = &measurements[i..i+WINDOW_SIZE];
-        let window_sum = window.iter().sum::<u32>();
+fn count_increases(measurements: &[u32]) -> u32 {
+    let mut last_measurement: Option<u32> = None;
+    let mut num_increases: u32 = 0;
 
-        windowed_measurements.push(window_sum);
+    for measurement in measurements {
+        if let Some(last) = last_measurement {
+            if *measurement > last {
+                num_increases += 1;
+            }
+        }
+        last_measurement = Some(*measurement);
     }
+    num_increases
+}
 
-    let windowed_increases = count_increases(&windowed_measurements);
-    if DEBUG { println!("There were {} windowed increases", windowed_increases); }
-
-    Answer::U32(increases, windowed_increases)
+example_tests! {
+    DepthMeasurements;
+    example: "01.txt" => 7, 5;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::read_input;
 
     #[test]
-    fn increase_count() {
-        let input = read_input("../testinputs/01.txt");
-        if let Answer::U32(increases, _) = depth_measurements(input) {
-            assert_eq!(7, increases);
-        } else { panic!("Answer was not a U32 variant.") }
+    fn bad_measurement() {
+        let input = Input::from("199\n2o0");
+        let error = DepthMeasurements::parse(&input).unwrap_err();
+        assert_eq!((Some(2), Some(1)), (error.line, error.column));
     }
 
     #[test]
-    fn windowed_increase_count() {
-        let input = read_input("../testinputs/01.txt");
-        if let Answer::U32(_, increases) = depth_measurements(input) {
-            assert_eq!(5, increases);
-        } else { panic!("Answer was not a U32 variant.") }
+    fn too_short_for_a_window() {
+        let input = DepthMeasurements::parse(&Input::from("199\n200")).unwrap();
+        assert_eq!(Ok(Answer::U32(0)), DepthMeasurements::part2(&input));
     }
 }
@@ -1,96 +1,132 @@
 // Day two
 
-pub fn sub_position(input: Vec<String>) -> (u32, u32) {
-    const DEBUG: bool = false;
+use crate::Answer;
+use crate::error::{Error, Result};
+use crate::input::Input;
+use crate::solver::Solver;
 
-    enum SubMovement {
-        Forward(u32),
-        Down(u32),
-        Up(u32),
-    }
+pub struct SubPosition;
 
-    let movements: Vec<SubMovement> = input.iter()
-        .map(|item| {
-            let line: Vec<&str> = item.split_whitespace().collect();
-            let mvt = line[0];
-            let magnitude = line[1].parse::<u32>().unwrap();
-            match mvt as &str {
-                "forward" => SubMovement::Forward(magnitude),
-                "up" => SubMovement::Up(magnitude),
-                "down" => SubMovement::Down(magnitude),
-                _ => unreachable!(),
-            }
-        }).collect();
+impl Solver for SubPosition {
+    type Parsed = Vec<SubMovement>;
 
-    struct SubPosition {
-        depth: u32,
-        distance: u32,
-        aim: u32,
+    fn parse(input: &Input) -> Result<Self::Parsed> {
+        input.lines()
+            .map(|line| {
+                let fields: Vec<&str> = line.fields().collect();
+                let &[mvt, magnitude] = &fields[..] else {
+                    return Err(line.error("a command and a distance"));
+                };
+                let magnitude = line.parse::<u32>(magnitude, "a distance")?;
+                match mvt {
+                    "forward" => Ok(SubMovement::Forward(magnitude)),
+                    "up" => Ok(SubMovement::Up(magnitude)),
+                    "down" => Ok(SubMovement::Down(magnitude)),
+                    _ => Err(line.error_at(mvt, "forward, up or down")),
+                }
+            }).collect()
     }
 
-    impl SubPosition {
-        fn product(&self) -> u32 {
-            self.depth * self.distance
+    fn part1(movements: &Self::Parsed) -> Result<Answer> {
+        let mut naive_position = Position::default();
+
+        for movement in movements {
+            match movement {
+                SubMovement::Forward(mag) => naive_position.distance = add(naive_position.distance, *mag)?,
+                SubMovement::Up(mag) => {
+                    naive_position.depth = naive_position.depth.checked_sub(*mag)
+                        .ok_or_else(|| Error::input("a course that stays underwater"))?;
+                },
+                SubMovement::Down(mag) => naive_position.depth = add(naive_position.depth, *mag)?,
+            }
         }
+
+        Ok(Answer::U32(naive_position.product()?))
     }
 
-    let mut naive_position = SubPosition {
-        depth: 0,
-        distance: 0,
-        aim: 0,
-    };
-
-    let mut position = SubPosition {
-        depth: 0,
-        distance: 0,
-        aim: 0,
-    };
-
-    for movement in movements {
-        match movement {
-            SubMovement::Forward(mag) => {
-                naive_position.distance += mag;
-                position.distance += mag;
-                position.depth += position.aim * mag;
-            },
-            SubMovement::Up(mag) => {
-                naive_position.depth -= mag;
-                position.aim -= mag;
-            },
-            SubMovement::Down(mag) => {
-                naive_position.depth += mag;
-                position.aim += mag;
+    fn part2(movements: &Self::Parsed) -> Result<Answer> {
+        let mut position = Position::default();
+
+        for movement in movements {
+            match movement {
+                SubMovement::Forward(mag) => {
+                    position.distance = add(position.distance, *mag)?;
+                    let descent = position.aim.checked_mul(*mag).ok_or_else(too_far)?;
+                    position.depth = add(position.depth, descent)?;
+                },
+                SubMovement::Up(mag) => {
+                    position.aim = position.aim.checked_sub(*mag)
+                        .ok_or_else(|| Error::input("a course that never aims above the surface"))?;
+                },
+                SubMovement::Down(mag) => position.aim = add(position.aim, *mag)?,
             }
         }
+
+        Ok(Answer::U32(position.product()?))
     }
+}
+
+// Answers are u32s, so everything along the way has to fit in one too.
+fn too_far() -> Error {
+    Error::input("a course whose sums fit in 32 bits")
+}
 
-    let naive_result = naive_position.product();
-    let position_result = position.product();
+fn add(a: u32, b: u32) -> Result<u32> {
+    a.checked_add(b).ok_or_else(too_far)
+}
+
+#[derive(Debug)]
+pub enum SubMovement {
+    Forward(u32),
+    Down(u32),
+    Up(u32),
+}
+
+#[derive(Default)]
+struct Position {
+    depth: u32,
+    distance: u32,
+    aim: u32,
+}
 
-    if DEBUG {
-        println!("The naive position vector product is {}", naive_result);
-        println!("The position vector product is {}", position_result);
+impl Position {
+    fn product(&self) -> Result<u32> {
+        self.depth.checked_mul(self.distance).ok_or_else(too_far)
     }
+}
 
-    (naive_result, position_result)
+example_tests! {
+    SubPosition;
+    example: "02.txt" => 150, 900;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::read_input;
 
     #[test]
-    fn naive_position_vector() {
-        let input = read_input("../testinputs/02.txt");
-        let (vector, _) = sub_position(input);
-        assert_eq!(150, vector);
+    fn unknown_command() {
+        let error = SubPosition::parse(&Input::from("forward 5\nbackward 2")).unwrap_err();
+        assert_eq!((Some(2), Some(1)), (error.line, error.column));
+        assert_eq!(Some("backward".to_string()), error.found);
+    }
+
+    #[test]
+    fn courses_too_long_to_count() {
+        let far = SubPosition::parse(&Input::from("forward 3000000000\nforward 3000000000")).unwrap();
+        assert!(SubPosition::part1(&far).is_err());
+        assert!(SubPosition::part2(&far).is_err());
+
+        // Each sum fits, but not the product
+        let deep = SubPosition::parse(&Input::from("forward 100000\ndown 100000")).unwrap();
+        assert!(SubPosition::part1(&deep).is_err());
+        let steep = SubPosition::parse(&Input::from("down 100000\nforward 100000")).unwrap();
+        assert!(SubPosition::part2(&steep).is_err());
     }
 
     #[test]
-    fn correct_position_vector() {
-        let input = read_input("../testinputs/02.txt");
-        let (_, vector) = sub_position(input);
-        assert_eq!(900, vector);
+    fn bad_distance() {
+        let error = SubPosition::parse(&Input::from("down  x")).unwrap_err();
+        assert_eq!((Some(1), Some(7)), (error.line, error.column));
     }
 }
@@ -1,6 +1,49 @@
 // Day three
 
-pub fn binary_diagnostic(mut diagnostics: Vec<String>) -> (u32, u32) {
+use crate::Answer;
+use crate::error::{Error, Result};
+use crate::input::Input;
+use crate::solver::Solver;
+
+pub struct BinaryDiagnostic;
+
+impl Solver for BinaryDiagnostic {
+    type Parsed = Vec<String>;
+
+    fn parse(input: &Input) -> Result<Self::Parsed> {
+        // Everything below assumes a non-empty list of equally long binary
+        // numbers that fit in a u32, so check that once up front.
+        let first = input.first_line("at least one diagnostic number")?;
+        let meas_len = first.text.len();
+        if meas_len == 0 || meas_len > 32 {
+            return Err(first.error("a binary number of 1 to 32 digits"));
+        }
+
+        input.lines()
+            .map(|line| {
+                if let Some(i) = line.text.find(|c| c != '0' && c != '1') {
+                    let c = line.text[i..].chars().next().unwrap();
+                    return Err(line.error_at(&line.text[i..i + c.len_utf8()], "a binary digit"));
+                }
+                if line.text.len() != meas_len {
+                    return Err(line.error(format!("{} binary digits", meas_len)));
+                }
+                Ok(line.text.to_string())
+            })
+            .collect()
+    }
+
+    fn part1(diagnostics: &Self::Parsed) -> Result<Answer> {
+        Ok(Answer::U64(power_consumption(diagnostics)))
+    }
+
+    fn part2(diagnostics: &Self::Parsed) -> Result<Answer> {
+        Ok(Answer::U64(life_support_rating(diagnostics.clone())))
+    }
+}
+
+// Both parts multiply two readings of up to 32 bits, so the answers need 64.
+fn power_consumption(diagnostics: &[String]) -> u64 {
     const DEBUG: bool = false;
 
     let meas_len = diagnostics[0].chars().count();
@@ -26,28 +69,28 @@ pub fn binary_diagnostic(mut diagnostics: Vec<String>) -> (u32, u32) {
 
     let bits = diag_digits.iter().fold((String::from(""), String::from("")), |mut acc, item| {
         if item * 2 > meas_count.try_into().unwrap() {
-            acc.0.push_str("1");
-            acc.1.push_str("0");
+            acc.0.push('1');
+            acc.1.push('0');
         } else {
-            acc.0.push_str("0");
-            acc.1.push_str("1");
+            acc.0.push('0');
+            acc.1.push('1');
         }
         acc
     });
     if DEBUG { println!("Bits are {:?}", bits); }
 
     struct Metrics {
-        gamma: u32,
-        epsilon: u32,
+        gamma: u64,
+        epsilon: u64,
     }
     impl Metrics {
-        fn power_consumption(&self) -> u32 {
+        fn power_consumption(&self) -> u64 {
             self.gamma * self.epsilon
         }
     }
     let rate = Metrics {
-        gamma: u32::from_str_radix(&bits.0, 2).unwrap(),
-        epsilon: u32::from_str_radix(&bits.1, 2).unwrap(),
+        gamma: u64::from_str_radix(&bits.0, 2).unwrap(),
+        epsilon: u64::from_str_radix(&bits.1, 2).unwrap(),
     };
     if DEBUG {
         println!("Submarine metrics are {} gamma, {} epsilon", rate.gamma, rate.epsilon);
@@ -56,6 +99,12 @@ pub fn binary_diagnostic(mut diagnostics: Vec<String>) -> (u32, u32) {
     let pow_consump = rate.power_consumption();
     if DEBUG { println!("submarine power consumption is {}", pow_consump); }
 
+    pow_consump
+}
+
+fn life_support_rating(mut diagnostics: Vec<String>) -> u64 {
+    const DEBUG: bool = false;
+
     // What we're doing next is...
     // Recursively(?) searching through progressively smaller slices of the vec
     // to find out which single item satisfies the rule.
@@ -71,8 +120,8 @@ pub fn binary_diagnostic(mut diagnostics: Vec<String>) -> (u32, u32) {
     // Actually yeah, lets see if we can do Vec<&[u8]> and then
     // parse the result from UTF-8 once we're done.
 
-    // This algorithm requires sorting the initial array.
-    // We still own diagnostics and we've retained our first result already.
+    // This algorithm requires sorting the initial array,
+    // so we take our own copy of the diagnostics.
     diagnostics.sort_unstable();
     if DEBUG { println!("Sorted diagnostics {:?}", diagnostics); }
 
@@ -102,7 +151,7 @@ pub fn binary_diagnostic(mut diagnostics: Vec<String>) -> (u32, u32) {
     let oxygen_rating = utf8bin_to_u32(&diagnostics[oxygen_rating_idx]);
     let co2_rating = utf8bin_to_u32(&diagnostics[co2_rating_idx]);
 
-    (pow_consump, oxygen_rating * co2_rating)
+    u64::from(oxygen_rating) * u64::from(co2_rating)
 }
 
 fn transpose<T>(outer: &[Vec<T>]) -> Vec<Vec<T>>
@@ -189,6 +238,16 @@ mod function_tests {
         assert_eq!(42, utf8bin_to_u32(&input));
     }
 
+    #[test]
+    fn widest_numbers_do_not_overflow() {
+        let high = "1".repeat(16) + &"0".repeat(16);
+        let low = "0".repeat(16) + &"1".repeat(16);
+        let text = [high.as_str(), high.as_str(), low.as_str()].join("\n");
+        let diagnostics = BinaryDiagnostic::parse(&Input::from(text.as_str())).unwrap();
+        assert_eq!(281466386841600, power_consumption(&diagnostics));
+        assert_eq!(281466386841600, life_support_rating(diagnostics));
+    }
+
     #[test]
     fn transposition() {
         let input: Vec<Vec<u8>> = vec![
@@ -249,22 +308,24 @@ mod apply_rule_tests {
     }
 }
 
+example_tests! {
+    BinaryDiagnostic;
+    example: "03.txt" => 198, 230;
+}
+
 #[cfg(test)]
-mod answer_tests {
+mod parse_tests {
     use super::*;
-    use crate::read_input;
 
     #[test]
-    fn power_consumption() {
-        let input = read_input("../testinputs/03.txt");
-        let (consumption, _) = binary_diagnostic(input);
-        assert_eq!(198, consumption);
+    fn rejects_non_binary_digit() {
+        let error = BinaryDiagnostic::parse(&Input::from("00100\n11210")).unwrap_err();
+        assert_eq!((Some(2), Some(3)), (error.line, error.column));
     }
 
     #[test]
-    fn life_support_rating() {
-        let input = read_input("../testinputs/03.txt");
-        let (_, rating) = binary_diagnostic(input);
-        assert_eq!(230, rating);
+    fn rejects_ragged_lines() {
+        let error = BinaryDiagnostic::parse(&Input::from("00100\n1110")).unwrap_err();
+        assert_eq!(Some(2), error.line);
     }
 }
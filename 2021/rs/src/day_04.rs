@@ -1,40 +1,249 @@
-pub fn play_bingo(input: Vec<String>) -> (u32, u32) {
-    // Parse input (strip the first line, split on double-newlines,
-    // Join with space, then split on whitespace to parse as ints.
-    let mut input_lines = input.iter();
-
-    let draws: Vec<u32> = input_lines.next().unwrap().split(',')
-        .map(|s| s.parse().unwrap()).collect();
-
-    // Need to do this silly shadowing pattern because of E0716
-    let grids = input_lines.cloned().collect::<Vec<String>>().join(" ");
-    let grids = grids.split_whitespace().collect::<Vec<&str>>();
-
-    // Create a vector of BingoBoards and instantiate them
-    let mut games: Vec<BingoBoard> = grids.chunks(25)
-        .map(|chunk|
-            BingoBoard::from(chunk.iter()
-                .map(|x| x.parse().unwrap())
-                .collect::<Vec<u32>>())
-        ).collect();
-
-    // Loop through the draws, loop through the boards until a winning draw
-    // Then score the winning board
-    let (mut first, mut last) = (0u32, 0u32);
-    for draw in &draws {
-        for game in &mut games.iter_mut().filter(|i| !i.won) {
-            if let Some(winner) = game.play(draw) {
-                if first == 0 {
-                    first = game.score(winner);
+// Day 4
+
+use crate::Answer;
+use crate::error::{Error, Result};
+use crate::grid::{Grid, Pos};
+use crate::input::Input;
+use crate::solver::Solver;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+pub struct PlayBingo;
+
+impl Solver for PlayBingo {
+    type Parsed = Bingo;
+
+    fn parse(input: &Input) -> Result<Self::Parsed> {
+        // The first block is the line of draws, and every block after it is a
+        // board, as many rows and columns as its block has.
+        let mut blocks = input.blocks();
+
+        let draw_block = blocks.next().ok_or_else(|| Error::input("a line of draws"))?;
+        // Anything straight after the draws is a board that's lost its blank line
+        if let Some(line) = draw_block.get(1) {
+            return Err(line.error("a blank line between the draws and the first board"));
+        }
+        let draws: Vec<u32> = draw_block[0].csv("a drawn number")?;
+
+        // Create a vector of BingoBoards and instantiate them
+        let boards: Vec<BingoBoard> = blocks
+            .map(|block| {
+                let rows = block.iter()
+                    .map(|line| line.values("a board number"))
+                    .collect::<Result<Vec<Vec<u32>>>>()?;
+                let width = rows[0].len();
+                if let Some((line, _)) = block.iter().zip(&rows).find(|(_, row)| row.len() != width) {
+                    return Err(line.error(format!("a row of {} numbers, like the board's first", width)));
+                }
+                Ok(BingoBoard::from(Grid::from_rows(rows).unwrap()))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Bingo { draws, boards, rules: WinRules::PUZZLE })
+    }
+
+    fn part1(bingo: &Self::Parsed) -> Result<Answer> {
+        let winner = bingo.play().first().cloned().ok_or_else(no_winner)?;
+        Ok(Answer::U128(winner.score))
+    }
+
+    fn part2(bingo: &Self::Parsed) -> Result<Answer> {
+        let winner = bingo.play().last().cloned().ok_or_else(no_winner)?;
+        Ok(Answer::U128(winner.score))
+    }
+}
+
+fn no_winner() -> Error {
+    Error::input("draws that let at least one board win")
+}
+
+#[derive(Debug)]
+pub struct Bingo {
+    draws: Vec<u32>,
+    boards: Vec<BingoBoard>,
+    rules: WinRules,
+}
+
+// Which completed patterns win a board. Squid bingo only has rows and
+// columns, but hall bingo plays all sorts of games.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WinRules {
+    pub rows: bool,
+    pub columns: bool,
+    // Both corner-to-corner diagonals; only square boards have them.
+    pub diagonals: bool,
+    pub corners: bool,
+    // Every square on the board.
+    pub blackout: bool,
+    // Start with the centre square marked, on boards that have one.
+    pub free_centre: bool,
+}
+
+impl WinRules {
+    pub const PUZZLE: WinRules = WinRules {
+        rows: true,
+        columns: true,
+        diagonals: false,
+        corners: false,
+        blackout: false,
+        free_centre: false,
+    };
+
+    // The usual 75-ball game: any line or the four corners, with a free centre.
+    pub const CLASSIC: WinRules = WinRules {
+        rows: true,
+        columns: true,
+        diagonals: true,
+        corners: true,
+        blackout: false,
+        free_centre: true,
+    };
+}
+
+impl Bingo {
+    pub fn with_rules(self, rules: WinRules) -> Self {
+        Bingo { rules, ..self }
+    }
+
+    // Play a fresh copy of every board through all of the draws, logging
+    // each board as it wins.
+    pub fn play(&self) -> GameLog {
+        let mut engine = Engine::new(&self.boards, &self.rules);
+
+        let mut events = Vec::new();
+        for (turn, draw) in self.draws.iter().enumerate() {
+            for (board, lines, score) in engine.draw(*draw) {
+                events.push(WinEvent { turn, number: *draw, board, lines, score });
+            }
+        }
+
+        GameLog { events }
+    }
+}
+
+// Plays every board at once without scanning them. An index says which
+// squares of which boards hold each number, so a draw only touches the squares
+// it marks, and every pattern on every board keeps a running count of its
+// marks so finishing one is noticed straight away.
+struct Engine {
+    games: Vec<BingoBoard>,
+    rules: WinRules,
+    index: HashMap<u32, Vec<(usize, Pos)>>,
+    tallies: Vec<Tally>,
+}
+
+struct Tally {
+    // Marks so far in each pattern, laid out by `Axis::slot`.
+    marks: Vec<usize>,
+    // Wide enough that neither this nor the score can overflow, whatever
+    // size the board and its numbers.
+    unmarked_sum: u64,
+}
+
+impl Engine {
+    fn new(boards: &[BingoBoard], rules: &WinRules) -> Self {
+        let games: Vec<BingoBoard> = boards.iter().map(|board| board.start(rules)).collect();
+
+        let mut index: HashMap<u32, Vec<(usize, Pos)>> = HashMap::new();
+        let mut tallies = Vec::with_capacity(games.len());
+        for (board, game) in games.iter().enumerate() {
+            let (width, height) = (game.board.width(), game.board.height());
+            let mut tally = Tally { marks: vec![0; width + height + 4], unmarked_sum: 0 };
+            for (pos, space) in game.board.iter() {
+                match space {
+                    Unmarked(number) => {
+                        index.entry(*number).or_default().push((board, pos));
+                        tally.unmarked_sum += u64::from(*number);
+                    },
+                    // Only the free centre starts out marked
+                    Marked => {
+                        for axis in game.axes_through(pos, rules) {
+                            tally.marks[axis.slot(width, height)] += 1;
+                        }
+                    },
+                }
+            }
+            tallies.push(tally);
+        }
+
+        Engine { games, rules: *rules, index, tallies }
+    }
+
+    // Mark `number` everywhere it appears, returning the boards it made win
+    // in board order, along with the patterns they finished and their score.
+    fn draw(&mut self, number: u32) -> Vec<(usize, Vec<Axis>, u128)> {
+        let mut finished: BTreeMap<usize, Vec<Axis>> = BTreeMap::new();
+
+        for &(board, pos) in self.index.get(&number).into_iter().flatten() {
+            let game = &mut self.games[board];
+            if game.won || game.board[pos] == Marked {
+                continue;
+            }
+            game.board[pos] = Marked;
+
+            let tally = &mut self.tallies[board];
+            tally.unmarked_sum -= u64::from(number);
+            let (width, height) = (game.board.width(), game.board.height());
+            for axis in game.axes_through(pos, &self.rules) {
+                let marks = &mut tally.marks[axis.slot(width, height)];
+                *marks += 1;
+                if *marks == game.pattern_size(axis) {
+                    finished.entry(board).or_default().push(axis);
                 }
-                last = game.score(winner);
-                // Remove this game from games
-                game.won = true;
             }
         }
-    };
 
-    (first, last)
+        finished.into_iter()
+            .map(|(board, mut lines)| {
+                self.games[board].won = true;
+                lines.sort();
+                (board, lines, u128::from(number) * u128::from(self.tallies[board].unmarked_sum))
+            })
+            .collect()
+    }
+}
+
+// One board winning. Boards only win once; after that they sit out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WinEvent {
+    // Which draw it was, counting from 0, and the number drawn.
+    pub turn: usize,
+    pub number: u32,
+    pub board: usize,
+    // Every pattern the winning number completed, which can be more than one.
+    pub lines: Vec<Axis>,
+    pub score: u128,
+}
+
+// Every win in a game, in the order they happened. Boards that win on the
+// same draw go in board order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameLog {
+    pub events: Vec<WinEvent>,
+}
+
+impl GameLog {
+    pub fn first(&self) -> Option<&WinEvent> {
+        self.events.first()
+    }
+
+    pub fn last(&self) -> Option<&WinEvent> {
+        self.events.last()
+    }
+
+    // The `k`th board to win, counting from 0.
+    pub fn nth(&self, k: usize) -> Option<&WinEvent> {
+        self.events.get(k)
+    }
+
+    // When `board` won, if it ever did.
+    pub fn win_of(&self, board: usize) -> Option<&WinEvent> {
+        self.events.iter().find(|event| event.board == board)
+    }
+
+    // Boards that never won.
+    pub fn losers(&self, boards: usize) -> Vec<usize> {
+        (0..boards).filter(|&board| self.win_of(board).is_none()).collect()
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -44,53 +253,110 @@ enum BingoSpace {
 }
 use BingoSpace::{Marked, Unmarked};
 
+#[derive(Clone, Debug)]
 struct BingoBoard {
-    board: [BingoSpace; 25],
-    plays: u32,
+    board: Grid<BingoSpace>,
     won: bool,
 }
 
-impl From<&[u32; 25]> for BingoBoard {
-    fn from(grid: &[u32; 25]) -> Self {
-        BingoBoard {
-            board: grid.iter()
-                .map(|item| Unmarked(*item))
-                .collect::<Vec<BingoSpace>>().try_into().unwrap(),
-            plays: 0,
-            won: false,
-        }
+impl From<Grid<u32>> for BingoBoard {
+    fn from(grid: Grid<u32>) -> Self {
+        BingoBoard::from(grid.map(|item| Unmarked(*item)))
     }
 }
 
-impl From<Vec<u32>> for BingoBoard {
-    fn from(grid: Vec<u32>) -> Self {
+impl From<Grid<BingoSpace>> for BingoBoard {
+    fn from(board: Grid<BingoSpace>) -> Self {
         BingoBoard {
-            board: grid.iter()
-                .map(|item| Unmarked(*item))
-                .collect::<Vec<BingoSpace>>().try_into().unwrap(),
-            plays: 0,
+            board,
             won: false,
         }
     }
 }
 
-impl From<Vec<BingoSpace>> for BingoBoard {
-    fn from(grid: Vec<BingoSpace>) -> Self {
-        BingoBoard {
-            board: grid.try_into().unwrap(),
-            plays: 0,
-            won: false,
+impl BingoBoard {
+    // A copy ready to play under `rules`.
+    fn start(&self, rules: &WinRules) -> Self {
+        let mut board = self.clone();
+        if let Some(centre) = board.centre().filter(|_| rules.free_centre) {
+            board.board[centre] = Marked;
+        }
+        board
+    }
+
+    // Only boards with an odd number of rows and columns have a middle square.
+    fn centre(&self) -> Option<(usize, usize)> {
+        let (width, height) = (self.board.width(), self.board.height());
+        (width % 2 == 1 && height % 2 == 1).then_some((width / 2, height / 2))
+    }
+
+    // The winning patterns `pos` is part of, under `rules`.
+    fn axes_through(&self, (x, y): (usize, usize), rules: &WinRules) -> Vec<Axis> {
+        let (width, height) = (self.board.width(), self.board.height());
+        let square = width == height;
+        let corner = (x == 0 || x == width - 1) && (y == 0 || y == height - 1);
+
+        let mut axes = Vec::new();
+        if rules.rows {
+            axes.push(Axis::Horizontal(y));
+        }
+        if rules.columns {
+            axes.push(Axis::Vertical(x));
+        }
+        if rules.diagonals && square && x == y {
+            axes.push(Axis::Diagonal);
+        }
+        if rules.diagonals && square && x + y == width - 1 {
+            axes.push(Axis::AntiDiagonal);
+        }
+        if rules.corners && corner {
+            axes.push(Axis::Corners);
+        }
+        if rules.blackout {
+            axes.push(Axis::Blackout);
+        }
+        axes
+    }
+
+    fn cells(&self, axis: Axis) -> Vec<(usize, usize)> {
+        let (width, height) = (self.board.width(), self.board.height());
+        match axis {
+            Axis::Horizontal(y) => (0..width).map(|x| (x, y)).collect(),
+            Axis::Vertical(x) => (0..height).map(|y| (x, y)).collect(),
+            Axis::Diagonal => (0..width).map(|i| (i, i)).collect(),
+            Axis::AntiDiagonal => (0..width).map(|i| (width - 1 - i, i)).collect(),
+            Axis::Corners => {
+                // A board one square wide or high has fewer than four
+                let mut corners = vec![(0, 0), (width - 1, 0), (0, height - 1), (width - 1, height - 1)];
+                corners.sort();
+                corners.dedup();
+                corners
+            },
+            Axis::Blackout => self.board.positions().collect(),
+        }
+    }
+
+    // How many squares `axis` covers on this board.
+    fn pattern_size(&self, axis: Axis) -> usize {
+        let (width, height) = (self.board.width(), self.board.height());
+        match axis {
+            Axis::Horizontal(_) | Axis::Diagonal | Axis::AntiDiagonal => width,
+            Axis::Vertical(_) => height,
+            Axis::Corners => width.min(2) * height.min(2),
+            Axis::Blackout => width * height,
         }
     }
 }
 
-use std::collections::HashSet;
+// How boards got played before the engine, kept for checking it.
+#[cfg(test)]
 impl BingoBoard {
-    fn play(&mut self, draw: &u32) -> Option<u32> {
+    // Mark `draw`, returning every pattern it completed.
+    fn play(&mut self, draw: &u32, rules: &WinRules) -> Vec<Axis> {
         // Play this board with the draw by mutating the board
         // for any marked positions, and also check to see if that caused us
-        // to win by searching the x and y axes of the marked positions
-        // and returning true if the board has won.
+        // to win by searching the lines through the marked positions
+        // and returning the ones that are now complete.
         //
         // Note that it is possible for one draw to mark multiple positions,
         // and for those positions to have a shared axis.
@@ -98,65 +364,273 @@ impl BingoBoard {
         // So, we play the draw by marking any positions found
         // and returning those positions, then derive the unique search axes
         // for those positions, then check the search axes.
-        //
-        // Maybe we should also keep an internal count of the number of
-        // draws that have been played so that we can skip searching for a win
-        // before the fifth draw has been played.
-        self.plays += 1;
-
-        let mut found = Vec::new();
-
-        for (i, space) in self.board.iter_mut().enumerate() {
-            if let Unmarked(number) = space {
-                if number == draw {
-                    found.push(i);
-                    *space = Marked;
-                }
-            }
-        }
+        let found: Vec<(usize, usize)> = self.board.iter()
+            .filter(|(_, space)| **space == Unmarked(*draw))
+            .map(|(pos, _)| pos)
+            .collect();
 
-        if self.plays <5 {
-            return None;
+        for &pos in &found {
+            self.board[pos] = Marked;
         }
 
         // derive axes for each found choice and dedupe them
         let mut axes = HashSet::new();
-        for i in found {
-            axes.insert(Axis::Horizontal((i / 5).try_into().unwrap()));
-            axes.insert(Axis::Vertical((i % 5).try_into().unwrap()));
-        }
-
-        // search each axis for all Marked spaces and return Some(draw) if found
-        for axis in axes {
-            let all_marked = self.board.iter().enumerate()
-                .filter(|(i, _)| {
-                    match axis {
-                        Axis::Horizontal(y) => i / 5 == y.into(),
-                        Axis::Vertical(x) => i % 5 == x.into(),
-                    }
-                }).all(|(_, space)| *space == Marked);
-            if all_marked {
-                return Some(*draw);
-            }
+        for pos in found {
+            axes.extend(self.axes_through(pos, rules));
         }
 
-        None
+        // search each axis for all Marked spaces
+        let mut complete: Vec<Axis> = axes.into_iter()
+            .filter(|axis| self.cells(*axis).iter().all(|pos| self.board[*pos] == Marked))
+            .collect();
+        complete.sort();
+        complete
     }
 
-    fn score(&self, winning_number: u32) -> u32 {
-        winning_number * self.board.iter().filter_map(|space| {
+    // Draw number times the unmarked squares.
+    fn score(&self, winning_number: u32) -> u128 {
+        u128::from(winning_number) * self.board.as_slice().iter().filter_map(|space| {
             match space {
                 Marked => None,
-                Unmarked(score) => Some(score),
+                Unmarked(score) => Some(u128::from(*score)),
             }
-        }).sum::<u32>()
+        }).sum::<u128>()
+    }
+}
+
+// A pattern of squares that wins when they're all marked.
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy)]
+pub enum Axis {
+    Horizontal(usize),
+    Vertical(usize),
+    // Top left to bottom right.
+    Diagonal,
+    // Top right to bottom left.
+    AntiDiagonal,
+    Corners,
+    Blackout,
+}
+
+impl Axis {
+    // Where this pattern's count lives in a board's tally: the rows, then
+    // the columns, then one each for the rest.
+    fn slot(self, width: usize, height: usize) -> usize {
+        match self {
+            Axis::Horizontal(y) => y,
+            Axis::Vertical(x) => height + x,
+            Axis::Diagonal => height + width,
+            Axis::AntiDiagonal => height + width + 1,
+            Axis::Corners => height + width + 2,
+            Axis::Blackout => height + width + 3,
+        }
+    }
+}
+
+// Rigging the draw: the shortest order of draws, taken from the numbers in
+// the draw line, that makes one board win first or last.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    // Win before any other board, with nobody else winning on the same draw.
+    First,
+    // Win after every other board has already won.
+    Last,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DrawOrder {
+    // Nothing shorter works.
+    Shortest(Vec<u32>),
+    // No order of the available numbers can do it.
+    Impossible(Proof),
+    // The search ran out of budget, maybe after finding an order that
+    // works but might not be the shortest.
+    GaveUp(Option<Vec<u32>>),
+}
+
+// Why a board can't be made to win the way we want.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Proof {
+    // For winning first: each pattern on the board, and what rules it out.
+    EveryPatternBlocked(Vec<(Axis, Blocker)>),
+    // This board can't win at all with the numbers that get drawn.
+    CannotWin(usize),
+    // For winning last: every way of letting the others win first was tried.
+    Exhausted,
+    // There's no board with that number to win with.
+    NoSuchBoard,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Blocker {
+    // A number the pattern needs never comes up.
+    Undrawn(u32),
+    // Drawing the whole pattern finishes one of this board's patterns too.
+    Beaten(usize),
+}
+
+// The numbers still needed for each winning pattern of each board.
+type Patterns = Vec<Vec<(Axis, Vec<u32>)>>;
+
+impl Bingo {
+    // Only the set of numbers drawn matters for who has won by the end, so
+    // winning first comes down to finding the smallest pattern that can be
+    // drawn without completing a pattern anywhere else. Winning last means
+    // covering a pattern on every other board first, which is a search; it
+    // gives up after visiting `budget` partial covers.
+    pub fn rig(&self, board: usize, goal: Goal, budget: usize) -> DrawOrder {
+        if board >= self.boards.len() {
+            return DrawOrder::Impossible(Proof::NoSuchBoard);
+        }
+        let pool: HashSet<u32> = self.draws.iter().copied().collect();
+        let patterns: Patterns = self.boards.iter()
+            .map(|b| b.start(&self.rules).patterns(&self.rules))
+            .collect();
+
+        match goal {
+            Goal::First => rig_first(board, &patterns, &pool),
+            Goal::Last => {
+                let drawable = |needs: &Vec<u32>| needs.iter().all(|n| pool.contains(n));
+                if let Some(stuck) = (0..patterns.len()).find(|&b| !patterns[b].iter().any(|(_, needs)| drawable(needs))) {
+                    return DrawOrder::Impossible(Proof::CannotWin(stuck));
+                }
+                let mut search = LastSearch { board, patterns: &patterns, pool: &pool, budget, best: None, gave_up: false };
+                search.run();
+                match (search.best, search.gave_up) {
+                    (best, true) => DrawOrder::GaveUp(best),
+                    (Some(best), false) => DrawOrder::Shortest(best),
+                    (None, false) => DrawOrder::Impossible(Proof::Exhausted),
+                }
+            },
+        }
+    }
+}
+
+fn rig_first(board: usize, patterns: &Patterns, pool: &HashSet<u32>) -> DrawOrder {
+    let mut blocked = Vec::new();
+    let mut best: Option<&Vec<u32>> = None;
+
+    for (axis, needs) in &patterns[board] {
+        if let Some(&missing) = needs.iter().find(|n| !pool.contains(n)) {
+            blocked.push((*axis, Blocker::Undrawn(missing)));
+            continue;
+        }
+        let drawn: HashSet<u32> = needs.iter().copied().collect();
+        let beaten_by = (0..patterns.len())
+            .find(|&other| other != board && completes_any(&patterns[other], &drawn));
+        match beaten_by {
+            Some(other) => blocked.push((*axis, Blocker::Beaten(other))),
+            None if best.is_none_or(|best| needs.len() < best.len()) => best = Some(needs),
+            None => {},
+        }
+    }
+
+    match best {
+        Some(needs) => DrawOrder::Shortest(needs.clone()),
+        None => DrawOrder::Impossible(Proof::EveryPatternBlocked(blocked)),
     }
 }
 
-#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
-enum Axis {
-    Horizontal(u8),
-    Vertical(u8),
+fn completes_any(patterns: &[(Axis, Vec<u32>)], drawn: &HashSet<u32>) -> bool {
+    patterns.iter().any(|(_, needs)| needs.iter().all(|n| drawn.contains(n)))
+}
+
+// Branch and bound for winning last. The board's final number `x` is drawn
+// on its own at the end; everything before it has to finish a pattern on
+// every other board, without `x`, and without finishing any of ours.
+struct LastSearch<'a> {
+    board: usize,
+    patterns: &'a Patterns,
+    pool: &'a HashSet<u32>,
+    budget: usize,
+    best: Option<Vec<u32>>,
+    gave_up: bool,
+}
+
+impl LastSearch<'_> {
+    fn run(&mut self) {
+        let ours = &self.patterns[self.board];
+        for (_, needs) in ours.iter().filter(|(_, needs)| needs.iter().all(|n| self.pool.contains(n))) {
+            for &last in needs {
+                let drawn: HashSet<u32> = needs.iter().copied().filter(|&n| n != last).collect();
+                if !completes_any(ours, &drawn) {
+                    self.cover(drawn, last);
+                }
+            }
+        }
+    }
+
+    fn cover(&mut self, drawn: HashSet<u32>, last: u32) {
+        if self.budget == 0 {
+            self.gave_up = true;
+            return;
+        }
+        self.budget -= 1;
+
+        // Each unfinished board needs at least this many more numbers, so we
+        // can stop as soon as that can't beat what we've got.
+        let options = |other: usize| -> Vec<&Vec<u32>> {
+            self.patterns[other].iter()
+                .map(|(_, needs)| needs)
+                .filter(|needs| needs.iter().all(|n| self.pool.contains(n) && *n != last))
+                .collect()
+        };
+        let unfinished: Vec<usize> = (0..self.patterns.len())
+            .filter(|&other| other != self.board && !completes_any(&self.patterns[other], &drawn))
+            .collect();
+        let bound = unfinished.iter()
+            .map(|&other| options(other).iter().map(|needs| needs.iter().filter(|n| !drawn.contains(n)).count()).min())
+            .try_fold(0, |bound, fewest| fewest.map(|fewest| bound.max(fewest)));
+        let Some(bound) = bound else {
+            // Some board can't finish without `last`
+            return;
+        };
+        if self.best.as_ref().is_some_and(|best| drawn.len() + bound + 1 >= best.len()) {
+            return;
+        }
+
+        let Some(&next) = unfinished.first() else {
+            let mut order: Vec<u32> = drawn.into_iter().collect();
+            order.sort_unstable();
+            order.push(last);
+            self.best = Some(order);
+            return;
+        };
+
+        let mut choices = options(next);
+        choices.sort_by_key(|needs| needs.iter().filter(|n| !drawn.contains(n)).count());
+        for needs in choices {
+            let mut more = drawn.clone();
+            more.extend(needs.iter().copied());
+            if !completes_any(&self.patterns[self.board], &more) {
+                self.cover(more, last);
+            }
+        }
+    }
+}
+
+impl BingoBoard {
+    // Every pattern that can win under `rules`, with the numbers it still
+    // needs, once each.
+    fn patterns(&self, rules: &WinRules) -> Vec<(Axis, Vec<u32>)> {
+        let mut axes = BTreeSet::new();
+        for pos in self.board.positions() {
+            axes.extend(self.axes_through(pos, rules));
+        }
+
+        axes.into_iter()
+            .map(|axis| {
+                let mut needs: Vec<u32> = self.cells(axis).into_iter()
+                    .filter_map(|pos| match self.board[pos] {
+                        Unmarked(number) => Some(number),
+                        Marked => None,
+                    })
+                    .collect();
+                needs.sort_unstable();
+                needs.dedup();
+                (axis, needs)
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -165,58 +639,54 @@ mod function_tests {
 
     #[test]
     fn play_airball() {
-        let mut board = BingoBoard::from(&[7u32; 25]);
-        board.plays = 5;
-        assert_eq!(None, board.play(&8));
+        let mut board = BingoBoard::from(Grid::new(5, 5, 7u32));
+        assert_eq!(Vec::<Axis>::new(), board.play(&8, &WinRules::PUZZLE));
     }
 
     #[test]
     fn play_nothing_but_net() {
-        let mut board = BingoBoard::from(&[7u32; 25]);
-        board.plays = 4;
-        assert_eq!(Some(7), board.play(&7));
+        let mut board = BingoBoard::from(Grid::new(5, 5, 7u32));
+        assert_eq!(10, board.play(&7, &WinRules::PUZZLE).len());
     }
 
     #[test]
     fn play_vertical_win() {
-        let mut board = BingoBoard::from(vec![
+        let mut board = BingoBoard::from(Grid::from_vec(5, vec![
             Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3), Marked,
             Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3), Marked,
             Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(7),
             Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3), Marked,
             Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3), Marked,
-        ]);
-        board.plays = 4;
-        assert_eq!(Some(7), board.play(&7));
+        ]));
+        assert_eq!(vec![Axis::Vertical(4)], board.play(&7, &WinRules::PUZZLE));
     }
 
     #[test]
     fn play_horizontal_win() {
-        let mut board = BingoBoard::from(vec![
+        let mut board = BingoBoard::from(Grid::from_vec(5, vec![
             Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3),
             Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3),
             Marked, Unmarked(7), Marked, Unmarked(7), Marked,
             Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3),
             Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3), Unmarked(3),
-        ]);
-        board.plays = 4;
-        assert_eq!(Some(7), board.play(&7));
+        ]));
+        assert_eq!(vec![Axis::Horizontal(2)], board.play(&7, &WinRules::PUZZLE));
     }
 
     #[test]
     fn play_game() {
-        let mut board = BingoBoard::from(&[
+        let mut board = BingoBoard::from(Grid::from_vec(5, vec![
              1u32, 2,  3,  4,  5,
              6,    7,  8,  9, 10,
             11,   12, 13, 14, 15,
             16,   17, 18, 19, 20,
             21,   22, 23, 24, 25,
-        ]);
-        assert_eq!(None, board.play(&2));
-        assert_eq!(None, board.play(&22));
-        assert_eq!(None, board.play(&12));
-        assert_eq!(None, board.play(&17));
-        assert_eq!(Some(7), board.play(&7));
+        ]));
+        assert!(board.play(&2, &WinRules::PUZZLE).is_empty());
+        assert!(board.play(&22, &WinRules::PUZZLE).is_empty());
+        assert!(board.play(&12, &WinRules::PUZZLE).is_empty());
+        assert!(board.play(&17, &WinRules::PUZZLE).is_empty());
+        assert_eq!(vec![Axis::Vertical(1)], board.play(&7, &WinRules::PUZZLE));
     }
 
     #[test]
@@ -224,28 +694,364 @@ mod function_tests {
         let mut grid = vec![Marked; 5];
         let mut spaces = vec![Unmarked(3); 20];
         grid.append(& mut spaces);
-        let board = BingoBoard::from(grid);
+        let board = BingoBoard::from(Grid::from_vec(5, grid));
 
         assert_eq!(300, board.score(5));
     }
 }
 
 #[cfg(test)]
-mod answer_tests {
+mod rules_tests {
+    use super::*;
+
+    fn board(width: usize, numbers: Vec<u32>) -> BingoBoard {
+        BingoBoard::from(Grid::from_vec(width, numbers))
+    }
+
+    fn rules(change: impl FnOnce(&mut WinRules)) -> WinRules {
+        let mut rules = WinRules { rows: false, columns: false, ..WinRules::PUZZLE };
+        change(&mut rules);
+        rules
+    }
+
+    // The draw that wins the board, playing a fresh copy of it.
+    fn winning_draws(board: &BingoBoard, rules: &WinRules, draws: &[u32]) -> Option<u32> {
+        let mut game = board.start(rules);
+        draws.iter().find(|draw| !game.play(draw, rules).is_empty()).copied()
+    }
+
+    #[test]
+    fn rectangular_boards() {
+        let board = board(4, (1..=8).collect());
+        assert_eq!(Some(8), winning_draws(&board, &WinRules::PUZZLE, &[5, 6, 7, 8]));
+        assert_eq!(Some(6), winning_draws(&board, &WinRules::PUZZLE, &[2, 6]));
+    }
+
+    #[test]
+    fn diagonals() {
+        let board = board(3, (1..=9).collect());
+        let diagonals = rules(|r| r.diagonals = true);
+        assert_eq!(Some(9), winning_draws(&board, &diagonals, &[1, 5, 9]));
+        assert_eq!(Some(7), winning_draws(&board, &diagonals, &[3, 5, 7]));
+        assert_eq!(None, winning_draws(&board, &diagonals, &[1, 2, 3]));
+
+        // Rectangles don't have any
+        let board = self::board(3, (1..=6).collect());
+        assert_eq!(None, winning_draws(&board, &diagonals, &[1, 5, 3, 4]));
+    }
+
+    #[test]
+    fn corners_and_blackout() {
+        let board = board(3, (1..=9).collect());
+        let corners = rules(|r| r.corners = true);
+        assert_eq!(Some(9), winning_draws(&board, &corners, &[1, 3, 5, 7, 9]));
+        let blackout = rules(|r| r.blackout = true);
+        assert_eq!(Some(2), winning_draws(&board, &blackout, &[9, 8, 7, 6, 5, 4, 3, 1, 2]));
+        assert_eq!(None, winning_draws(&board, &blackout, &[9, 8, 7, 6, 5, 4, 3, 1]));
+    }
+
+    #[test]
+    fn free_centre() {
+        let board = board(5, (1..=25).collect());
+        assert_eq!(Some(15), winning_draws(&board, &WinRules::CLASSIC, &[11, 12, 14, 15]));
+        assert_eq!(Some(19), winning_draws(&board, &WinRules::CLASSIC, &[1, 7, 25, 19]));
+        assert_eq!(None, winning_draws(&board, &WinRules::PUZZLE, &[11, 12, 14, 15]));
+
+        // The free square doesn't count towards the score
+        let mut game = board.start(&WinRules::CLASSIC);
+        assert!(game.play(&15, &WinRules::CLASSIC).is_empty());
+        assert_eq!((325 - 13 - 15) * 2, game.score(2));
+
+        // An even board has no middle to give away
+        assert_eq!(None, self::board(2, vec![1, 2, 3, 4]).centre());
+    }
+
+    #[test]
+    fn example_under_other_rules() {
+        let bingo = PlayBingo::parse(&crate::read_input("../testinputs/04.txt")).unwrap();
+        assert_eq!(4512, bingo.play().first().unwrap().score);
+        let classic = bingo.with_rules(WinRules::CLASSIC).play();
+        assert_ne!(4512, classic.first().unwrap().score);
+    }
+}
+
+#[cfg(test)]
+mod log_tests {
+    use super::*;
+
+    fn example_log() -> GameLog {
+        PlayBingo::parse(&crate::read_input("../testinputs/04.txt")).unwrap().play()
+    }
+
+    #[test]
+    fn example_game() {
+        let log = example_log();
+        let first = log.first().unwrap();
+        assert_eq!((11, 24, 2, 4512), (first.turn, first.number, first.board, first.score));
+        assert_eq!(vec![Axis::Horizontal(0)], first.lines);
+
+        let last = log.last().unwrap();
+        assert_eq!((14, 13, 1, 1924), (last.turn, last.number, last.board, last.score));
+        assert_eq!(Some(last), log.nth(2));
+        assert_eq!(None, log.nth(3));
+        assert_eq!(Some(13), log.win_of(1).map(|event| event.number));
+        assert_eq!(Vec::<usize>::new(), log.losers(3));
+    }
+
+    #[test]
+    fn scores_too_big_for_a_u32() {
+        let bingo = PlayBingo::parse(&Input::from("3000000000,1\n\n3000000000 3000000000\n1 4000000000")).unwrap();
+        assert_eq!(Ok(Answer::U128(3000000000 * 4000000001)), PlayBingo::part1(&bingo));
+    }
+
+    #[test]
+    fn zero_score_still_wins_first() {
+        // The first board's unmarked numbers are all 0, so it scores nothing,
+        // and that mustn't be mistaken for nobody having won yet.
+        let bingo = PlayBingo::parse(&Input::from("1,2,3,4\n\n1 2\n0 0\n\n3 4\n6 7\n\n8 9\n5 5")).unwrap();
+        let log = bingo.play();
+        assert_eq!(Some(0), log.first().map(|event| event.score));
+        assert_eq!(Ok(Answer::U128(0)), PlayBingo::part1(&bingo));
+        assert_eq!(Ok(Answer::U128(52)), PlayBingo::part2(&bingo));
+        assert_eq!(vec![2], log.losers(3));
+    }
+
+    #[test]
+    fn nobody_wins() {
+        let bingo = PlayBingo::parse(&Input::from("9\n\n1 2\n3 4")).unwrap();
+        assert!(bingo.play().events.is_empty());
+        assert!(PlayBingo::part1(&bingo).is_err());
+        assert!(PlayBingo::part2(&bingo).is_err());
+    }
+}
+
+#[cfg(test)]
+mod engine_tests {
+    use super::*;
+    use crate::testing::Lcg;
+
+    // The straightforward way, playing each board in turn with
+    // `BingoBoard::play`, to check the engine against.
+    fn play_by_scanning(bingo: &Bingo) -> GameLog {
+        let mut games: Vec<BingoBoard> = bingo.boards.iter()
+            .map(|board| board.start(&bingo.rules))
+            .collect();
+
+        let mut events = Vec::new();
+        for (turn, draw) in bingo.draws.iter().enumerate() {
+            for (board, game) in games.iter_mut().enumerate().filter(|(_, game)| !game.won) {
+                let lines = game.play(draw, &bingo.rules);
+                if !lines.is_empty() {
+                    events.push(WinEvent { turn, number: *draw, board, lines, score: game.score(*draw) });
+                    game.won = true;
+                }
+            }
+        }
+        GameLog { events }
+    }
+
+    // A made-up game with lots of boards and repeated numbers, from a
+    // little linear congruential generator so it's the same every time.
+    fn random_bingo(boards: usize, width: usize, height: usize, pool: u32) -> Bingo {
+        let mut rng = Lcg::new(0x2021_0004);
+        let mut next = move || rng.below(u64::from(pool)) as u32;
+
+        let boards = (0..boards)
+            .map(|_| BingoBoard::from(Grid::from_vec(width, (0..width * height).map(|_| next()).collect())))
+            .collect();
+        let draws = (0..pool * 2).map(|_| next()).collect();
+        Bingo { draws, boards, rules: WinRules::PUZZLE }
+    }
+
+    #[test]
+    fn agrees_with_scanning_on_the_example() {
+        let bingo = PlayBingo::parse(&crate::read_input("../testinputs/04.txt")).unwrap();
+        assert_eq!(play_by_scanning(&bingo), bingo.play());
+    }
+
+    #[test]
+    fn agrees_with_scanning_under_every_rule() {
+        for rules in [
+            WinRules::PUZZLE,
+            WinRules::CLASSIC,
+            WinRules { blackout: true, ..WinRules::CLASSIC },
+            WinRules { rows: false, columns: false, corners: true, ..WinRules::PUZZLE },
+        ] {
+            for (width, height) in [(5, 5), (3, 4), (1, 3)] {
+                let bingo = random_bingo(200, width, height, 40).with_rules(rules);
+                let log = bingo.play();
+                assert!(!log.events.is_empty());
+                assert_eq!(play_by_scanning(&bingo), log, "{:?} on {}x{}", rules, width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn pattern_sizes() {
+        let board = BingoBoard::from(Grid::new(1, 3, 0u32));
+        assert_eq!(2, board.pattern_size(Axis::Corners));
+        assert_eq!(board.cells(Axis::Corners).len(), board.pattern_size(Axis::Corners));
+        assert_eq!(3, board.pattern_size(Axis::Vertical(0)));
+    }
+}
+
+#[cfg(test)]
+mod rig_tests {
+    use super::*;
+
+    fn bingo(text: &str) -> Bingo {
+        PlayBingo::parse(&Input::from(text)).unwrap()
+    }
+
+    // Does drawing `order` get `board` the result we wanted?
+    fn achieves(bingo: &Bingo, order: &[u32], board: usize, goal: Goal) -> bool {
+        let log = Bingo { draws: order.to_vec(), boards: bingo.boards.clone(), rules: bingo.rules }.play();
+        let alone = |event: &WinEvent| log.events.iter().filter(|e| e.turn == event.turn).count() == 1;
+        let event = match goal {
+            Goal::First => log.first(),
+            Goal::Last if log.events.len() == bingo.boards.len() => log.last(),
+            Goal::Last => None,
+        };
+        event.is_some_and(|event| event.board == board && alone(event))
+    }
+
+    // The shortest working order's length, by trying every order of every
+    // selection of the numbers drawn.
+    fn brute_force(bingo: &Bingo, board: usize, goal: Goal) -> Option<usize> {
+        fn orders(pool: &[u32], len: usize, order: &mut Vec<u32>, found: &mut dyn FnMut(&[u32]) -> bool) -> bool {
+            if order.len() == len {
+                return found(order);
+            }
+            for &n in pool {
+                if !order.contains(&n) {
+                    order.push(n);
+                    let done = orders(pool, len, order, found);
+                    order.pop();
+                    if done {
+                        return true;
+                    }
+                }
+            }
+            false
+        }
+
+        let mut pool = bingo.draws.clone();
+        pool.sort_unstable();
+        pool.dedup();
+        (1..=pool.len()).find(|&len| {
+            orders(&pool, len, &mut Vec::new(), &mut |order| achieves(bingo, order, board, goal))
+        })
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let games = [
+            bingo("1,2,3,4,5,6\n\n1 2\n3 4\n\n3 5\n6 1\n\n2 6\n5 4"),
+            bingo("1,2,3,4,5\n\n1 2\n3 4\n\n1 2\n5 5\n\n4 3\n2 1"),
+            bingo("1,2,3,4,5,6,7\n\n1 2 3\n4 5 6\n\n7 1\n2 3\n4 5"),
+        ];
+        for (game, bingo) in games.iter().enumerate() {
+            for board in 0..bingo.boards.len() {
+                for goal in [Goal::First, Goal::Last] {
+                    let expected = brute_force(bingo, board, goal);
+                    let context = format!("game {} board {} {:?}", game, board, goal);
+                    match bingo.rig(board, goal, 100_000) {
+                        DrawOrder::Shortest(order) => {
+                            assert_eq!(expected, Some(order.len()), "{}", context);
+                            assert!(achieves(bingo, &order, board, goal), "{}", context);
+                        },
+                        DrawOrder::Impossible(_) => assert_eq!(None, expected, "{}", context),
+                        DrawOrder::GaveUp(_) => panic!("{} gave up", context),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blocked_patterns() {
+        let bingo = bingo("1,3,2,4\n\n1 2\n3 4\n\n1 2\n7 8");
+        assert_eq!(DrawOrder::Impossible(Proof::EveryPatternBlocked(vec![
+            (Axis::Horizontal(0), Blocker::Beaten(0)),
+            (Axis::Horizontal(1), Blocker::Undrawn(7)),
+            (Axis::Vertical(0), Blocker::Undrawn(7)),
+            (Axis::Vertical(1), Blocker::Undrawn(8)),
+        ])), bingo.rig(1, Goal::First, 100));
+        // Board 1 can only win with 1 and 2, which finishes board 0 as well
+        assert_eq!(DrawOrder::Impossible(Proof::Exhausted), bingo.rig(0, Goal::Last, 100));
+
+        let bingo = self::bingo("1,2\n\n1 2\n3 4\n\n7 8\n9 10");
+        assert_eq!(DrawOrder::Impossible(Proof::CannotWin(1)), bingo.rig(0, Goal::Last, 100));
+    }
+
+    #[test]
+    fn unknown_board() {
+        let bingo = bingo("1,2\n\n1 2\n3 4");
+        for goal in [Goal::First, Goal::Last] {
+            assert_eq!(DrawOrder::Impossible(Proof::NoSuchBoard), bingo.rig(1, goal, 100));
+        }
+    }
+
+    #[test]
+    fn example_boards() {
+        let bingo = PlayBingo::parse(&crate::read_input("../testinputs/04.txt")).unwrap();
+        for board in 0..3 {
+            for goal in [Goal::First, Goal::Last] {
+                let DrawOrder::Shortest(order) = bingo.rig(board, goal, 1_000_000) else {
+                    panic!("board {} couldn't be made to win {:?}", board, goal);
+                };
+                assert!(achieves(&bingo, &order, board, goal));
+                if goal == Goal::First {
+                    assert_eq!(5, order.len());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn runs_out_of_budget() {
+        let bingo = PlayBingo::parse(&crate::read_input("../testinputs/04.txt")).unwrap();
+        assert_eq!(DrawOrder::GaveUp(None), bingo.rig(0, Goal::Last, 0));
+    }
+}
+
+example_tests! {
+    PlayBingo;
+    example: "04.txt" => 4512, 1924;
+}
+
+#[cfg(test)]
+mod parse_tests {
     use super::*;
     use crate::read_input;
 
     #[test]
-    fn winning_bingo_board() {
+    fn incomplete_board() {
         let input = read_input("../testinputs/04.txt");
-        let (score, _) = play_bingo(input);
-        assert_eq!(4512, score);
+        let input = input.text().trim_end().rsplit_once(' ').unwrap().0;
+        let error = PlayBingo::parse(&Input::from(input)).unwrap_err();
+        assert_eq!(Some(19), error.line);
     }
 
     #[test]
-    fn losingest_bingo_board() {
-        let input = read_input("../testinputs/04.txt");
-        let (_, score) = play_bingo(input);
-        assert_eq!(1924, score);
+    fn board_shape_comes_from_the_block() {
+        let bingo = PlayBingo::parse(&Input::from("1,2\n\n1 2 3\n4 5 6\n\n7 8\n9 10\n11 12")).unwrap();
+        let shapes: Vec<(usize, usize)> = bingo.boards.iter()
+            .map(|board| (board.board.width(), board.board.height()))
+            .collect();
+        assert_eq!(vec![(3, 2), (2, 3)], shapes);
+    }
+
+    #[test]
+    fn board_stuck_to_the_draws() {
+        let error = PlayBingo::parse(&Input::from("1,2,3\n1 2\n3 4\n\n3 4\n1 2")).unwrap_err();
+        assert_eq!(Some(2), error.line);
+        assert_eq!(Some("1 2".to_string()), error.found);
+    }
+
+    #[test]
+    fn bad_draw() {
+        let error = PlayBingo::parse(&Input::from("7,4,,5")).unwrap_err();
+        assert_eq!((Some(1), Some(5)), (error.line, error.column));
     }
 }
@@ -1,149 +1,376 @@
 // Day 5
-use std::cmp::{min, max};
+use crate::Answer;
+use crate::error;
+use crate::grid::Grid;
+use crate::input::{Input, Line};
+use crate::netpbm::Image;
+use crate::solver::{Renders, Rendering, Solver};
+use std::cmp::{max, min};
+use std::collections::HashMap;
 use std::fmt;
 
-pub fn hydrothermal_vents(input: Vec<String>) -> (u32, u32) {
-    // Parse input to line segments
-    let vents: Vec<LineSegment> = input.iter()
-        .map(|item| item.parse().unwrap())
-        // .map(|item| LineSegment::from(item.as_str()))
-        .collect();
+pub struct HydrothermalVents;
 
-    // Reduce to max x and y values in grid
-    let (x_max, y_max) = vents.iter()
-        .fold((0u32, 0u32), |(x_max, y_max), curr| {
-            (
-                max(max(curr.0.x, curr.1.x), x_max),
-                max(max(curr.0.y, curr.1.y), y_max)
-            )
-        });
+impl Solver for HydrothermalVents {
+    type Parsed = Vec<LineSegment>;
 
-    // Instantiate a coordinate grid with the max values
-    let mut grid = Grid::new(x_max, y_max);
-
-    // Plot each isolinear(?) line segment on the grid
-    for vent in vents.iter().filter(|s| !s.is_diagonal()) {
-        grid.plot(&vent);
+    fn parse(input: &Input) -> error::Result<Self::Parsed> {
+        // Parse input to line segments
+        input.lines()
+            .map(|line| LineSegment::parse(&line))
+            .collect()
     }
 
-    // println!("Isolinear grid looks like this {:?}", grid);
+    fn part1(vents: &Self::Parsed) -> error::Result<Answer> {
+        // Only the isolinear(?) line segments count for the first part
+        let isolinear: Vec<&LineSegment> = vents.iter()
+            .filter(|s| s.slope() == Slope::AxisAligned)
+            .collect();
+
+        Ok(Answer::U64(count_overlaps(&isolinear, Raster::default())))
+    }
 
-    // Count overlaps
-    let overlaps = grid.count_overlaps();
+    fn part2(vents: &Self::Parsed) -> error::Result<Answer> {
+        let vents: Vec<&LineSegment> = vents.iter().collect();
 
-    // Additively plot diagonal line segments on the grid
-    for vent in vents.iter().filter(|s| s.is_diagonal()) {
-        grid.plot(&vent);
+        Ok(Answer::U64(count_overlaps(&vents, Raster::default())))
     }
 
-    // println!("Full grid looks like this {:?}", grid);
+    // Heat maps of how many vents cover each point, for each part's set of
+    // vents, as long as the floor is small enough to draw. Floors small
+    // enough to read also get the diagram the puzzle draws, to check against.
+    fn render(vents: &Self::Parsed) -> Renders {
+        let all: Vec<&LineSegment> = vents.iter().collect();
+        let isolinear: Vec<&LineSegment> = vents.iter()
+            .filter(|s| s.slope() == Slope::AxisAligned)
+            .collect();
+
+        // Both parts on the same canvas so the pictures line up
+        let bounds = Bounds::of(&all);
+        if bounds.area() > DENSE_CELL_LIMIT {
+            return Vec::new();
+        }
+        let readable = bounds.width() <= DIAGRAM_LIMIT && bounds.height() <= DIAGRAM_LIMIT;
+        [("part1", "part1_diagram", isolinear), ("part2", "part2_diagram", all)].into_iter()
+            .flat_map(|(name, diagram_name, vents)| {
+                let mut map = VentMap::new(bounds);
+                for vent in vents {
+                    map.plot(vent, Raster::default());
+                }
+                let diagram = readable.then(|| (diagram_name, Rendering::Text(map.to_string())));
+                std::iter::once((name, map.heat_map().into())).chain(diagram)
+            })
+            .collect()
+    }
+}
 
-    (overlaps, grid.count_overlaps())
+// Points covered by at least two of `vents`, drawing them with `raster`.
+pub fn count_overlaps(vents: &[&LineSegment], raster: Raster) -> u64 {
+    count_overlaps_with(vents, Backend::choose(vents, raster), raster)
 }
 
-struct Grid {
-    matrix: Vec<u32>,
-    width: u32,
+// Past this many cells a dense map costs more memory than we're willing to
+// spend on it, however busy the floor is.
+const DENSE_CELL_LIMIT: u64 = 1 << 24;
+// The widest or tallest floor that's still worth reading as text.
+const DIAGRAM_LIMIT: u64 = 100;
+// And a dense map that's mostly empty space isn't worth sweeping either.
+const DENSE_CELLS_PER_POINT: u64 = 32;
+
+// How to keep count of the vents at each point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Backend {
+    // A grid just big enough to cover every vent.
+    Dense,
+    // Only the points some vent actually passes through.
+    Sparse,
 }
 
-impl Grid {
-    fn new(x_max: u32, y_max: u32) -> Self {
-        let x_size = x_max + 1;
-        let y_size = y_max + 1;
-        Grid {
-            matrix: vec![0; (x_size * y_size).try_into().unwrap()],
-            width: x_size,
+impl Backend {
+    // Dense for the puzzle's compact thousand-square floors, sparse once the
+    // coordinates get big and the vents are spread thin across them.
+    fn choose(vents: &[&LineSegment], raster: Raster) -> Self {
+        let area = Bounds::of(vents).area();
+        let points: u64 = vents.iter().map(|vent| vent.len(raster)).sum();
+
+        if area <= DENSE_CELL_LIMIT && area <= points.saturating_mul(DENSE_CELLS_PER_POINT) {
+            Backend::Dense
+        } else {
+            Backend::Sparse
         }
     }
+}
+
+fn count_overlaps_with(vents: &[&LineSegment], backend: Backend, raster: Raster) -> u64 {
+    let mut counter: Box<dyn VentCounter> = match backend {
+        Backend::Dense => Box::new(VentMap::new(Bounds::of(vents))),
+        Backend::Sparse => Box::new(SparseVents::default()),
+    };
+    for vent in vents {
+        counter.plot(vent, raster);
+    }
+    counter.count_overlaps()
+}
+
+// The smallest rectangle holding every vent, corners included. Vents can be
+// anywhere, so it doesn't have to start at the origin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Bounds {
+    min: (i64, i64),
+    max: (i64, i64),
+}
+
+impl Bounds {
+    fn of(vents: &[&LineSegment]) -> Self {
+        let mut ends = vents.iter().flat_map(|vent| [&vent.0, &vent.1]);
+        let Some(first) = ends.next() else {
+            return Bounds { min: (0, 0), max: (0, 0) };
+        };
+
+        ends.fold(Bounds { min: (first.x, first.y), max: (first.x, first.y) }, |bounds, end| {
+            Bounds {
+                min: (min(bounds.min.0, end.x), min(bounds.min.1, end.y)),
+                max: (max(bounds.max.0, end.x), max(bounds.max.1, end.y)),
+            }
+        })
+    }
 
-    fn plot(&mut self, seg: &LineSegment) {
-        // We need i32s to do math
-        let x0: i32 = seg.0.x.try_into().unwrap();
-        let y0: i32 = seg.0.y.try_into().unwrap();
-        let x1: i32 = seg.1.x.try_into().unwrap();
-        let y1: i32 = seg.1.y.try_into().unwrap();
-        let width: i32 = self.width.try_into().unwrap();
-
-        let xdiff = i32::abs(x0 - x1);
-        let ydiff = i32::abs(y0 - y1);
-
-        let steps = max(xdiff, ydiff);
-
-        let idx0 = x0 + y0 * width;
-        let idx1 = x1 + y1 * width;
-        let idxmin = min(idx0, idx1);
-        let idxmax = max(idx0, idx1);
-
-        let step_size: usize = ((idxmax - idxmin) / steps).try_into().unwrap();
-        // This is a little bit confusing. If steps == 1,
-        // then just incremend idx0 and idx1.
-        // If steps > 1, take (idx1 - idx0) / steps
-        // and increment idx1 by that amount steps times.
-        // There's probably a range by steps or something?
-        // Then I can iter over the range and increment
-        // the matrix values directly.
-        for i in (idxmin..=idxmax).step_by(step_size) {
-            let i: usize = i.try_into().unwrap();
-            self.matrix[i] += 1;
+    fn width(&self) -> u64 {
+        self.max.0.abs_diff(self.min.0).saturating_add(1)
+    }
+
+    fn height(&self) -> u64 {
+        self.max.1.abs_diff(self.min.1).saturating_add(1)
+    }
+
+    fn area(&self) -> u64 {
+        self.width().saturating_mul(self.height())
+    }
+}
+
+// Tallies how many vents cover each point of the ocean floor.
+trait VentCounter {
+    fn plot(&mut self, seg: &LineSegment, raster: Raster);
+    // Points covered by at least two vents.
+    fn count_overlaps(&self) -> u64;
+}
+
+struct VentMap {
+    // The floor coordinates of the grid's top left cell.
+    origin: (i64, i64),
+    counts: Grid<u32>,
+}
+
+impl VentMap {
+    fn new(bounds: Bounds) -> Self {
+        VentMap {
+            origin: bounds.min,
+            counts: Grid::new(bounds.width() as usize, bounds.height() as usize, 0),
         }
     }
+}
 
-    fn count_overlaps(&self) -> u32 {
-        self.matrix.iter().filter(|&x| *x > 1).count().try_into().unwrap()
+impl VentMap {
+    // Brighter where more vents cross, with the busiest point white.
+    fn heat_map(&self) -> Image {
+        let busiest = self.counts.as_slice().iter().copied().max().unwrap_or(0);
+        Image::Gray {
+            pixels: self.counts.map(|&count| min(count, 255) as u8),
+            max: busiest.clamp(1, 255) as u8,
+        }
     }
 }
 
-impl fmt::Debug for Grid {
+// The diagram from the puzzle: `.` where there are no vents, otherwise how
+// many there are, with `+` standing in for anything past 9.
+impl fmt::Display for VentMap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let width: usize = self.width.try_into().unwrap();
-        for row in (0..(self.matrix.len() / width)) {
-            let start_idx = row * width;
-            let end_idx = (row + 1) * width;
-            f.write_str("\n");
-            f.write_fmt(format_args!("{:?}", &self.matrix[start_idx..end_idx]));
+        f.write_str(&self.counts.render(|&count| match count {
+            0 => '.',
+            1..=9 => char::from_digit(count, 10).unwrap(),
+            _ => '+',
+        }))
+    }
+}
+
+impl VentCounter for VentMap {
+    fn plot(&mut self, seg: &LineSegment, raster: Raster) {
+        for (x, y) in seg.points(raster) {
+            self.counts[((x - self.origin.0) as usize, (y - self.origin.1) as usize)] += 1;
+        }
+    }
+
+    fn count_overlaps(&self) -> u64 {
+        self.counts.as_slice().iter().filter(|&x| *x > 1).count() as u64
+    }
+}
+
+#[derive(Debug, Default)]
+struct SparseVents {
+    counts: HashMap<(i64, i64), u32>,
+}
+
+impl VentCounter for SparseVents {
+    fn plot(&mut self, seg: &LineSegment, raster: Raster) {
+        for point in seg.points(raster) {
+            *self.counts.entry(point).or_insert(0) += 1;
         }
-        Ok(())
+    }
+
+    fn count_overlaps(&self) -> u64 {
+        self.counts.values().filter(|&x| *x > 1).count() as u64
     }
 }
 
 #[derive(Eq, PartialEq, Debug)]
 struct Coordinate {
-    x: u32,
-    y: u32,
+    x: i64,
+    y: i64,
 }
 
 #[derive(Eq, PartialEq, Debug)]
-struct LineSegment(Coordinate, Coordinate);
+pub struct LineSegment(Coordinate, Coordinate);
+
+// How steep a segment is, which decides which part it counts for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slope {
+    // Horizontal, vertical, or a single point.
+    AxisAligned,
+    Diagonal,
+    // Anything else, which the puzzle never gives us.
+    Other,
+}
+
+// Which points a segment covers when it isn't straight or at 45°.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Raster {
+    // Every column or row along the longer axis gets the nearest point, the
+    // way you'd draw it on screen.
+    #[default]
+    Bresenham,
+    // Only points exactly on the line, stepping by the slope reduced to its
+    // lowest terms.
+    Lattice,
+}
 
 impl LineSegment {
-    fn is_diagonal(&self) -> bool {
-        if self.0.x == self.1.x || self.0.y == self.1.y {
-            false
+    // Exactly `x1,y1 -> x2,y2`: no stray spaces, no extra numbers and
+    // nothing after the end.
+    fn parse(line: &Line) -> error::Result<Self> {
+        let (start, end) = line.split_once(" -> ", "a line segment like 'x1,y1 -> x2,y2'")?;
+
+        let coordinate = |text: &str| -> error::Result<Coordinate> {
+            let (x, y) = text.split_once(',')
+                .ok_or_else(|| line.error_at(text, "a coordinate like 'x,y'"))?;
+            Ok(Coordinate { x: parse_number(line, x)?, y: parse_number(line, y)? })
+        };
+
+        Ok(LineSegment(coordinate(start)?, coordinate(end)?))
+    }
+
+    pub fn slope(&self) -> Slope {
+        let (dx, dy) = self.deltas();
+        if dx == 0 || dy == 0 {
+            Slope::AxisAligned
+        } else if dx.abs() == dy.abs() {
+            Slope::Diagonal
         } else {
-            true
+            Slope::Other
+        }
+    }
+
+    fn is_diagonal(&self) -> bool {
+        self.slope() != Slope::AxisAligned
+    }
+
+    fn deltas(&self) -> (i64, i64) {
+        (self.1.x - self.0.x, self.1.y - self.0.y)
+    }
+
+    // How many points the segment covers, counting both ends.
+    pub fn len(&self, raster: Raster) -> u64 {
+        let (dx, dy) = self.deltas();
+        let (dx, dy) = (dx.unsigned_abs(), dy.unsigned_abs());
+        match raster {
+            Raster::Bresenham => max(dx, dy) + 1,
+            Raster::Lattice => gcd(dx, dy) + 1,
+        }
+    }
+
+    // Every point from one end to the other. Both rules agree on the
+    // axis-aligned and diagonal segments.
+    pub fn points(&self, raster: Raster) -> Box<dyn Iterator<Item = (i64, i64)>> {
+        let (x0, y0) = (self.0.x, self.0.y);
+        let (dx, dy) = self.deltas();
+
+        match raster {
+            Raster::Lattice => {
+                let steps = gcd(dx.unsigned_abs(), dy.unsigned_abs()) as i64;
+                let (step_x, step_y) = if steps == 0 { (0, 0) } else { (dx / steps, dy / steps) };
+                Box::new((0..=steps).map(move |i| (x0 + step_x * i, y0 + step_y * i)))
+            },
+            Raster::Bresenham => {
+                // The all-octant version, carrying the error term for both axes.
+                let (x1, y1) = (x0 + dx, y0 + dy);
+                let (width, height) = (dx.abs(), -dy.abs());
+                let (step_x, step_y) = (dx.signum(), dy.signum());
+                let mut err = width + height;
+                let mut next = Some((x0, y0));
+                Box::new(std::iter::from_fn(move || {
+                    let (x, y) = next?;
+                    next = if (x, y) == (x1, y1) {
+                        None
+                    } else {
+                        let double = 2 * err;
+                        let mut moved = (x, y);
+                        if double >= height {
+                            err += height;
+                            moved.0 += step_x;
+                        }
+                        if double <= width {
+                            err += width;
+                            moved.1 += step_y;
+                        }
+                        Some(moved)
+                    };
+                    Some((x, y))
+                }))
+            },
         }
     }
 }
 
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Digits with an optional minus sign; `str::parse` would also let a `+`
+// through.
+fn parse_number(line: &Line, token: &str) -> error::Result<i64> {
+    let digits = token.strip_prefix('-').unwrap_or(token);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(line.error_at(token, "a whole number"));
+    }
+    let expected = format!("a whole number between -{0} and {0}", COORDINATE_LIMIT);
+    let number: i64 = line.parse(token, &expected)?;
+    if !(-COORDINATE_LIMIT..=COORDINATE_LIMIT).contains(&number) {
+        return Err(line.error_at(token, expected));
+    }
+    Ok(number)
+}
+
+// Anything a u32 could hold, either side of zero, so the gap between two
+// points (and the Bresenham error term, doubled) is nowhere near overflowing.
+const COORDINATE_LIMIT: i64 = u32::MAX as i64;
+
 use std::str::FromStr;
-use std::error::Error;
 impl FromStr for LineSegment {
-    type Err = Box<dyn Error>;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut results = s.split(&[' ', ',', '-', '>'][..])
-            .filter(|x| !x.is_empty())
-            .map(|x| Result::<_, Self::Err>::Ok(x.parse()?));
-
-        let mut next = || {
-            Result::<_, Self::Err>::Ok(
-                results.next().ok_or_else(|| "Not enough parsed numerics.")??
-            )
-        };
+    type Err = error::Error;
 
-        Ok(LineSegment(
-            Coordinate { x: next()?, y: next()? },
-            Coordinate { x: next()?, y: next()? },
-        ))
+    // A segment on its own, so the error doesn't point at any line.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LineSegment::parse(&Line { number: 1, text: s })
+            .map_err(|error| error::Error { line: None, ..error })
     }
 }
 
@@ -181,7 +408,7 @@ mod function_tests {
 
     #[test]
     fn grid_size() {
-        let mut grid = Grid::new(4, 4);
+        let mut grid = VentMap::new(Bounds { min: (0, 0), max: (4, 4) });
         let expected: Vec<u32> = vec![
             0, 0, 0, 0, 0,
             0, 0, 0, 0, 0,
@@ -189,12 +416,12 @@ mod function_tests {
             0, 0, 0, 0, 0,
             0, 0, 0, 0, 0,
         ];
-        assert_eq!(expected, grid.matrix);
+        assert_eq!(expected, grid.counts.as_slice());
     }
 
     #[test]
     fn horizontal_plot() {
-        let mut grid = Grid::new(4, 4);
+        let mut grid = VentMap::new(Bounds { min: (0, 0), max: (4, 4) });
         let seg = LineSegment(Coordinate {x: 1, y: 2}, Coordinate {x: 4, y: 2});
         let expected: Vec<u32> = vec![
             0, 0, 0, 0, 0,
@@ -203,13 +430,13 @@ mod function_tests {
             0, 0, 0, 0, 0,
             0, 0, 0, 0, 0,
         ];
-        grid.plot(&seg);
-        assert_eq!(expected, grid.matrix);
+        grid.plot(&seg, Raster::Bresenham);
+        assert_eq!(expected, grid.counts.as_slice());
     }
 
     #[test]
     fn vertical_plot() {
-        let mut grid = Grid::new(4, 4);
+        let mut grid = VentMap::new(Bounds { min: (0, 0), max: (4, 4) });
         let seg = LineSegment(Coordinate {x: 1, y: 3}, Coordinate {x: 1, y: 0});
         let expected: Vec<u32> = vec![
             0, 1, 0, 0, 0,
@@ -218,13 +445,13 @@ mod function_tests {
             0, 1, 0, 0, 0,
             0, 0, 0, 0, 0,
         ];
-        grid.plot(&seg);
-        assert_eq!(expected, grid.matrix);
+        grid.plot(&seg, Raster::Bresenham);
+        assert_eq!(expected, grid.counts.as_slice());
     }
 
     #[test]
     fn up_diag_plog() {
-        let mut grid = Grid::new(4, 4);
+        let mut grid = VentMap::new(Bounds { min: (0, 0), max: (4, 4) });
         let seg = LineSegment(Coordinate {x: 2, y: 1}, Coordinate {x: 0, y: 3});
         let expected: Vec<u32> = vec![
             0, 0, 0, 0, 0,
@@ -233,13 +460,13 @@ mod function_tests {
             1, 0, 0, 0, 0,
             0, 0, 0, 0, 0,
         ];
-        grid.plot(&seg);
-        assert_eq!(expected, grid.matrix);
+        grid.plot(&seg, Raster::Bresenham);
+        assert_eq!(expected, grid.counts.as_slice());
     }
 
     #[test]
     fn down_diag_plot() {
-        let mut grid = Grid::new(4, 4);
+        let mut grid = VentMap::new(Bounds { min: (0, 0), max: (4, 4) });
         let seg = LineSegment(Coordinate {x: 0, y: 0}, Coordinate {x: 4, y: 4});
         let expected: Vec<u32> = vec![
             1, 0, 0, 0, 0,
@@ -248,36 +475,274 @@ mod function_tests {
             0, 0, 0, 1, 0,
             0, 0, 0, 0, 1,
         ];
-        grid.plot(&seg);
-        assert_eq!(expected, grid.matrix);
+        grid.plot(&seg, Raster::Bresenham);
+        assert_eq!(expected, grid.counts.as_slice());
     }
 
     #[test]
     fn overlap_counting() {
-        let grid = Grid {
-            matrix: vec![0, 1, 1, 2, 1, 3, 3, 2, 1, 0, 4],
-            width: 11,
+        let grid = VentMap {
+            origin: (0, 0),
+            counts: Grid::from_vec(11, vec![0, 1, 1, 2, 1, 3, 3, 2, 1, 0, 4]),
         };
         assert_eq!(5, grid.count_overlaps());
     }
 }
 
 #[cfg(test)]
-mod answer_tests {
+mod backend_tests {
     use super::*;
-    use crate::read_input;
+
+    fn segments(text: &str) -> Vec<LineSegment> {
+        HydrothermalVents::parse(&Input::from(text)).unwrap()
+    }
 
     #[test]
-    fn overlapping_isolinears() {
-        let input = read_input("../testinputs/05.txt");
-        let (points, _) = hydrothermal_vents(input);
-        assert_eq!(5, points);
+    fn backends_agree() {
+        let vents = segments("0,9 -> 5,9\n8,0 -> 0,8\n9,4 -> 3,4\n2,2 -> 2,1\n7,0 -> 7,4\n\
+            6,4 -> 2,0\n0,9 -> 2,9\n3,4 -> 1,4\n0,0 -> 8,8\n5,5 -> 8,2");
+        let vents: Vec<&LineSegment> = vents.iter().collect();
+        assert_eq!(12, count_overlaps_with(&vents, Backend::Dense, Raster::Bresenham));
+        assert_eq!(12, count_overlaps_with(&vents, Backend::Sparse, Raster::Bresenham));
     }
-    
+
+    #[test]
+    fn dense_only_when_the_floor_is_busy() {
+        let rows: Vec<String> = (0..1000).step_by(10).map(|y| format!("0,{} -> 999,{}", y, y)).collect();
+        let vents = segments(&rows.join("\n"));
+        assert_eq!(Backend::Dense, Backend::choose(&vents.iter().collect::<Vec<_>>(), Raster::Bresenham));
+
+        let vents = segments("0,0 -> 999,999\n0,999 -> 999,0");
+        assert_eq!(Backend::Sparse, Backend::choose(&vents.iter().collect::<Vec<_>>(), Raster::Bresenham));
+    }
+
+    #[test]
+    fn huge_sparse_floors() {
+        // A dense map of this would be four billion cells.
+        let vents = segments("0,0 -> 0,10\n0,5 -> 10,5\n4000000000,1 -> 4000000000,3\n\
+            4000000000,2 -> 3999999999,2\n5,999999 -> 5,999999");
+        let vents: Vec<&LineSegment> = vents.iter().collect();
+        assert_eq!(Backend::Sparse, Backend::choose(&vents, Raster::Bresenham));
+        assert_eq!(2, count_overlaps_with(&vents, Backend::Sparse, Raster::Bresenham));
+    }
+
+    #[test]
+    fn walks_both_ends() {
+        let seg = segments("3,1 -> 0,4").remove(0);
+        assert_eq!(4, seg.len(Raster::Bresenham));
+        assert_eq!(vec![(3, 1), (2, 2), (1, 3), (0, 4)], seg.points(Raster::Bresenham).collect::<Vec<_>>());
+    }
+}
+
+#[cfg(test)]
+mod raster_tests {
+    use super::*;
+
+    fn segment(text: &str) -> LineSegment {
+        LineSegment::from_str(text).unwrap()
+    }
+
+    fn points(text: &str, raster: Raster) -> Vec<(i64, i64)> {
+        segment(text).points(raster).collect()
+    }
+
     #[test]
-    fn all_overlapping() {
-        let input = read_input("../testinputs/05.txt");
-        let (_, points) = hydrothermal_vents(input);
-        assert_eq!(12, points);
+    fn slopes() {
+        assert_eq!(Slope::AxisAligned, segment("2,3 -> 2,5").slope());
+        assert_eq!(Slope::AxisAligned, segment("2,3 -> 7,3").slope());
+        assert_eq!(Slope::AxisAligned, segment("4,4 -> 4,4").slope());
+        assert_eq!(Slope::Diagonal, segment("5,5 -> 8,2").slope());
+        assert_eq!(Slope::Other, segment("0,0 -> 4,2").slope());
+    }
+
+    #[test]
+    fn bresenham_shallow_and_steep() {
+        // Halfway points round away from where the line started.
+        assert_eq!(vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)], points("0,0 -> 4,2", Raster::Bresenham));
+        assert_eq!(vec![(1, 3), (1, 2), (0, 1), (0, 0)], points("1,3 -> 0,0", Raster::Bresenham));
+        assert_eq!(5, segment("0,0 -> 4,2").len(Raster::Bresenham));
+    }
+
+    #[test]
+    fn lattice_points_only() {
+        assert_eq!(vec![(0, 0), (2, 1), (4, 2)], points("0,0 -> 4,2", Raster::Lattice));
+        assert_eq!(vec![(6, 0), (3, 2), (0, 4)], points("6,0 -> 0,4", Raster::Lattice));
+        assert_eq!(vec![(1, 3), (0, 0)], points("1,3 -> 0,0", Raster::Lattice));
+        assert_eq!(3, segment("6,0 -> 0,4").len(Raster::Lattice));
+    }
+
+    #[test]
+    fn overlaps_depend_on_the_rule() {
+        let vents = [segment("0,0 -> 4,2"), segment("1,1 -> 1,1"), segment("4,0 -> 0,2")];
+        let vents: Vec<&LineSegment> = vents.iter().collect();
+        // Bresenham rounds the first line through (1,1) as well as (2,1), but
+        // the lattice points of the two lines only meet at (2,1)
+        assert_eq!(2, count_overlaps(&vents, Raster::Bresenham));
+        assert_eq!(1, count_overlaps(&vents, Raster::Lattice));
+    }
+
+    #[test]
+    fn rules_agree_on_puzzle_slopes() {
+        for text in ["0,9 -> 5,9", "7,0 -> 7,4", "8,0 -> 0,8", "5,5 -> 8,2", "3,3 -> 3,3"] {
+            assert_eq!(points(text, Raster::Bresenham), points(text, Raster::Lattice), "{}", text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod picture_tests {
+    use super::*;
+
+    const EXAMPLE: &str = "0,9 -> 5,9\n8,0 -> 0,8\n9,4 -> 3,4\n2,2 -> 2,1\n7,0 -> 7,4\n\
+        6,4 -> 2,0\n0,9 -> 2,9\n3,4 -> 1,4\n0,0 -> 8,8\n5,5 -> 8,2";
+
+    fn example_map(vents: impl Fn(&LineSegment) -> bool) -> VentMap {
+        let mut map = VentMap::new(Bounds { min: (0, 0), max: (9, 9) });
+        for vent in HydrothermalVents::parse(&Input::from(EXAMPLE)).unwrap().iter().filter(|v| vents(v)) {
+            map.plot(vent, Raster::Bresenham);
+        }
+        map
+    }
+
+    #[test]
+    fn matches_the_puzzle_diagrams() {
+        let isolinear = example_map(|vent| vent.slope() == Slope::AxisAligned);
+        assert_eq!("\
+.......1..
+..1....1..
+..1....1..
+.......1..
+.112111211
+..........
+..........
+..........
+..........
+222111....
+", isolinear.to_string());
+
+        let all = example_map(|_| true);
+        assert_eq!("\
+1.1....11.
+.111...2..
+..2.1.111.
+...1.2.2..
+.112313211
+...1.2....
+..1...1...
+.1.....1..
+1.......1.
+222111....
+", all.to_string());
+    }
+
+    #[test]
+    fn busy_points() {
+        let map = VentMap { origin: (0, 0), counts: Grid::from_vec(3, vec![0, 10, 300]) };
+        assert_eq!(".++\n", map.to_string());
+    }
+
+    #[test]
+    fn heat_map_scales_to_the_busiest_point() {
+        let Image::Gray { pixels, max } = example_map(|_| true).heat_map() else {
+            panic!("expected a grayscale heat map");
+        };
+        assert_eq!(3, max);
+        assert_eq!(3, pixels[(4, 4)]);
+        assert_eq!(0, pixels[(1, 0)]);
+    }
+
+    #[test]
+    fn renders_both_parts() {
+        let vents = HydrothermalVents::parse(&Input::from(EXAMPLE)).unwrap();
+        let names: Vec<&str> = HydrothermalVents::render(&vents).iter().map(|(name, _)| *name).collect();
+        assert_eq!(vec!["part1", "part1_diagram", "part2", "part2_diagram"], names);
+        let renders = HydrothermalVents::render(&vents);
+        assert_eq!("txt", renders[3].1.extension());
+        assert_eq!(example_map(|_| true).to_string(), renders[3].1.contents());
+
+        // Too big to read, but not to draw
+        let wide = HydrothermalVents::parse(&Input::from("0,0 -> 100,0\n0,0 -> 0,5")).unwrap();
+        let names: Vec<&str> = HydrothermalVents::render(&wide).iter().map(|(name, _)| *name).collect();
+        assert_eq!(vec!["part1", "part2"], names);
+
+        let huge = HydrothermalVents::parse(&Input::from("0,0 -> 100000,100000")).unwrap();
+        assert!(HydrothermalVents::render(&huge).is_empty());
+    }
+}
+
+example_tests! {
+    HydrothermalVents;
+    example: "05.txt" => 5, 12;
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    #[test]
+    fn unparseable_segment() {
+        let input = Input::from("0,9 -> 5,9\n8,0 -> 0");
+        let error = HydrothermalVents::parse(&input).unwrap_err();
+        assert_eq!(Some(2), error.line);
+    }
+
+    #[test]
+    fn negative_coordinates() {
+        let expected = LineSegment(Coordinate { x: -3, y: 4 }, Coordinate { x: 5, y: -6 });
+        assert_eq!(expected, LineSegment::from_str("-3,4 -> 5,-6").unwrap());
+    }
+
+    #[test]
+    fn strict_grammar() {
+        for bad in [
+            "1,2 -> 3,4 junk",
+            "1,2 -> 3,4,5",
+            "1,2,3 -> 4,5",
+            "1,2 -> 3,4 -> 5,6",
+            "1, 2 -> 3,4",
+            "1,2->3,4",
+            "+1,2 -> 3,4",
+            "1,2 -> 3,",
+            "--1,2 -> 3,4",
+            "1,2 -> 3,99999999999999999999",
+            "-9223372036854775808,0 -> 9223372036854775807,0",
+            "0,4294967296 -> 0,0",
+            "0,0 -> -4294967296,0",
+        ] {
+            assert!(LineSegment::from_str(bad).is_err(), "{}", bad);
+        }
+
+        // As far apart as coordinates go, which still leaves room to work
+        // out the distance between them.
+        let widest = LineSegment::from_str("-4294967295,0 -> 4294967295,0").unwrap();
+        assert_eq!((1 << 33) - 1, widest.len(Raster::Bresenham));
+        let tallest = LineSegment::from_str("0,-4294967295 -> 1,4294967295").unwrap();
+        let start: Vec<(i64, i64)> = tallest.points(Raster::Bresenham).take(2).collect();
+        assert_eq!(vec![(0, -4294967295), (0, -4294967294)], start);
+        let error = LineSegment::from_str("-9223372036854775808,0 -> 9223372036854775807,0").unwrap_err();
+        assert_eq!(Some(1), error.column);
+    }
+
+    #[test]
+    fn error_points_at_the_junk() {
+        let input = Input::from("0,9 -> 5,9\n8,0 -> 0,8x");
+        let error = HydrothermalVents::parse(&input).unwrap_err();
+        assert_eq!((Some(2), Some(10)), (error.line, error.column));
+        assert_eq!(Some("8x".to_string()), error.found);
+    }
+
+    #[test]
+    fn floors_away_from_the_origin() {
+        let vents = HydrothermalVents::parse(&Input::from("-2,-1 -> 1,-1\n0,-3 -> 0,0\n-2,-3 -> 1,0")).unwrap();
+        let vents: Vec<&LineSegment> = vents.iter().collect();
+        assert_eq!(Bounds { min: (-2, -3), max: (1, 0) }, Bounds::of(&vents));
+
+        let mut map = VentMap::new(Bounds::of(&vents));
+        for vent in &vents {
+            map.plot(vent, Raster::Bresenham);
+        }
+        assert_eq!("1.1.\n.11.\n1131\n..11\n", map.to_string());
+        assert_eq!(1, count_overlaps_with(&vents, Backend::Dense, Raster::Bresenham));
+        assert_eq!(1, count_overlaps_with(&vents, Backend::Sparse, Raster::Bresenham));
     }
 }
@@ -1,33 +1,94 @@
 // Day 6
 
 use crate::Answer;
+use crate::bignum::BigUint;
+use crate::error;
+use crate::input::Input;
+use crate::solver::{Renders, Rendering, Solver};
+use std::fmt;
+use std::ops::{Add, Mul};
 
-pub fn lanternfish(input: Vec<String>) -> Answer {
-    let mut school: School = School::from_str(&input[0]).unwrap();
-    school.time_travel(80);
-    let first_count = school.census().try_into().unwrap();
-    school.time_travel(256-80);
-    let second_count = school.census().try_into().unwrap();
-    Answer::U64(first_count, second_count)
+pub struct Lanternfish;
+
+impl Solver for Lanternfish {
+    type Parsed = School;
+
+    fn parse(input: &Input) -> error::Result<Self::Parsed> {
+        let line = input.first_line("a line of fish ages")?;
+        let ages: Vec<u8> = line.csv("a fish age")?;
+        School::from_ages(&ages).map_err(|_| line.error("fish ages from 0 to 8"))
+    }
+
+    fn part1(school: &Self::Parsed) -> error::Result<Answer> {
+        Ok(Answer::U64(school.projected_census(80)))
+    }
+
+    fn part2(school: &Self::Parsed) -> error::Result<Answer> {
+        Ok(Answer::U64(school.projected_census(256)))
+    }
+
+    // Every day up to part 2's, for plotting how the school grows.
+    fn render(school: &Self::Parsed) -> Renders {
+        vec![("population", Rendering::Csv(school.history::<u128>(256).to_csv()))]
+    }
+}
+
+// How a population breeds. Everyone sits in a bucket for the number of days
+// left on their timer, and each day they all move down one. Whoever was at 0
+// spawns `offspring` newborns at `newborn` and goes back to `reset` themselves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lifecycle {
+    buckets: usize,
+    reset: usize,
+    newborn: usize,
+    offspring: u64,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct School(u64, u64, u64, u64, u64, u64, u64, u64, u64);
+impl Lifecycle {
+    // Seven days between spawns, two more for a newborn's first.
+    pub const LANTERNFISH: Lifecycle = Lifecycle { buckets: 9, reset: 6, newborn: 8, offspring: 1 };
+
+    // `buckets` can be more than the reset and newborn ages need, for
+    // populations that start out with longer timers than they ever get back.
+    pub fn new(buckets: usize, reset: usize, newborn: usize, offspring: u64) -> Result<Self, &'static str> {
+        if reset >= buckets || newborn >= buckets {
+            return Err("reset and newborn ages need a bucket each");
+        }
+        Ok(Lifecycle { buckets, reset, newborn, offspring })
+    }
+
+    pub fn buckets(&self) -> usize {
+        self.buckets
+    }
+
+    // One day's worth of moving down the buckets and spawning.
+    fn age<T: Count>(&self, counts: &mut [T]) {
+        let spawning = counts[0].clone();
+        counts.rotate_left(1);
+        counts[self.buckets - 1] = T::from_u64(0);
+        counts[self.reset] = counts[self.reset].clone() + spawning.clone();
+        counts[self.newborn] = counts[self.newborn].clone() + spawning * T::from_u64(self.offspring);
+    }
+}
+
+// Counts of fish by the days left on their timers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct School {
+    lifecycle: Lifecycle,
+    counts: Vec<u64>,
+}
 
 impl School {
-    fn age(&mut self) {
-        *self = School(
-            self.1,
-            self.2,
-            self.3,
-            self.4,
-            self.5,
-            self.6,
-            self.7 + self.0,
-            self.8,
-            self.0
-        );
+    pub fn empty(lifecycle: Lifecycle) -> Self {
+        School { lifecycle, counts: vec![0; lifecycle.buckets] }
+    }
+
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
 
+    fn age(&mut self) {
+        self.lifecycle.age(&mut self.counts);
     }
 
     fn time_travel(&mut self, ticks: u32) {
@@ -36,16 +97,227 @@ impl School {
         }
     }
 
+    // How many fish there will be after `ticks` days, leaving this school as is.
+    fn projected_census(&self, ticks: u32) -> u64 {
+        let mut school = self.clone();
+        school.time_travel(ticks);
+        school.census()
+    }
+
     fn census(&self) -> u64 {
-        self.0 +
-        self.1 +
-        self.2 +
-        self.3 +
-        self.4 +
-        self.5 +
-        self.6 +
-        self.7 +
-        self.8
+        self.counts.iter().sum()
+    }
+
+    // Today and each of the next `ticks` days, counted in whatever's big
+    // enough: u64 runs out after a few hundred days, BigUint never does.
+    pub fn history<T: Count>(&self, ticks: u32) -> History<T> {
+        let mut counts: Vec<T> = self.counts.iter().map(|&n| T::from_u64(n)).collect();
+        let mut days = vec![counts.clone()];
+        for tick in 0..ticks {
+            self.lifecycle.age(&mut counts);
+            days.push(counts.clone());
+        }
+        History { days }
+    }
+
+    // The first day there are more than `n` fish, or None if nobody's
+    // breeding and there never will be. Otherwise everyone spawns once their
+    // timer runs out, so the school keeps growing and we get there eventually.
+    //
+    // Counting in u128 means there's always room for one more day: up to
+    // then there were at most u64::MAX fish, and even if every one of them
+    // spawned u64::MAX more that's still short of u128::MAX.
+    pub fn first_day_over(&self, n: u64) -> Option<u32> {
+        let mut counts: Vec<u128> = self.counts.iter().map(|&count| u128::from(count)).collect();
+        let mut day = 0;
+        loop {
+            let census: u128 = counts.iter().sum();
+            if census > u128::from(n) {
+                return Some(day);
+            }
+            if census == 0 || self.lifecycle.offspring == 0 {
+                return None;
+            }
+            self.lifecycle.age(&mut counts);
+            day += 1;
+        }
+    }
+}
+
+// The counts in each bucket, day by day, starting from day 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct History<T> {
+    days: Vec<Vec<T>>,
+}
+
+impl<T: Count> History<T> {
+    pub fn day(&self, day: u32) -> Option<&[T]> {
+        self.days.get(day as usize).map(Vec::as_slice)
+    }
+
+    pub fn totals(&self) -> impl Iterator<Item = T> + '_ {
+        self.days.iter().map(|counts| counts.iter().cloned().fold(T::from_u64(0), |acc, n| acc + n))
+    }
+}
+
+impl<T: Count + PartialOrd> History<T> {
+    // Only as far as the history goes.
+    pub fn first_day_over(&self, n: u64) -> Option<u32> {
+        let n = T::from_u64(n);
+        self.totals().position(|total| total > n).map(|day| day as u32)
+    }
+}
+
+impl<T: Count + fmt::Display> History<T> {
+    // A header row, then one row per day: the day, each bucket, and the total.
+    pub fn to_csv(&self) -> String {
+        let buckets = self.days.first().map_or(0, Vec::len);
+        let mut csv = String::from("day");
+        for age in 0..buckets {
+            csv.push_str(&format!(",age_{}", age));
+        }
+        csv.push_str(",total\n");
+
+        for ((day, counts), total) in self.days.iter().enumerate().zip(self.totals()) {
+            csv.push_str(&day.to_string());
+            for count in counts {
+                csv.push_str(&format!(",{}", count));
+            }
+            csv.push_str(&format!(",{}\n", total));
+        }
+        csv
+    }
+}
+
+// Anything we can count fish in: plain integers while they fit, BigUint once
+// they don't, or Mod when all anyone wants is the remainder.
+pub trait Count: Clone + Add<Output = Self> + Mul<Output = Self> {
+    fn from_u64(n: u64) -> Self;
+}
+
+impl Count for u64 {
+    fn from_u64(n: u64) -> Self {
+        n
+    }
+}
+
+impl Count for u128 {
+    fn from_u64(n: u64) -> Self {
+        u128::from(n)
+    }
+}
+
+impl Count for BigUint {
+    fn from_u64(n: u64) -> Self {
+        BigUint::from(n)
+    }
+}
+
+// A count modulo M.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mod<const M: u64>(pub u64);
+
+impl<const M: u64> Add for Mod<M> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Mod(((u128::from(self.0) + u128::from(other.0)) % u128::from(M)) as u64)
+    }
+}
+
+impl<const M: u64> Mul for Mod<M> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Mod((u128::from(self.0) * u128::from(other.0) % u128::from(M)) as u64)
+    }
+}
+
+impl<const M: u64> Count for Mod<M> {
+    fn from_u64(n: u64) -> Self {
+        Mod(n % M)
+    }
+}
+
+// Square, row-major.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Matrix<T> {
+    size: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Count> Matrix<T> {
+    fn identity(size: usize) -> Self {
+        let cells = (0..size * size)
+            .map(|i| T::from_u64(u64::from(i % (size + 1) == 0)))
+            .collect();
+        Matrix { size, cells }
+    }
+
+    fn multiply(&self, other: &Self) -> Self {
+        let n = self.size;
+        let cells = (0..n * n)
+            .map(|i| {
+                let (row, column) = (i / n, i % n);
+                (0..n).fold(T::from_u64(0), |acc, k| {
+                    acc + self.cells[row * n + k].clone() * other.cells[k * n + column].clone()
+                })
+            })
+            .collect();
+        Matrix { size: n, cells }
+    }
+
+    // Square and multiply, so a trillion is only about eighty multiplications.
+    fn pow(&self, mut exponent: u64) -> Self {
+        let mut result = Matrix::identity(self.size);
+        let mut square = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&square);
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                square = square.multiply(&square);
+            }
+        }
+        result
+    }
+
+    fn apply(&self, counts: &[T]) -> Vec<T> {
+        self.cells.chunks(self.size)
+            .map(|row| {
+                row.iter().zip(counts)
+                    .fold(T::from_u64(0), |acc, (a, b)| acc + a.clone() * b.clone())
+            })
+            .collect()
+    }
+}
+
+impl Lifecycle {
+    // One day as a matrix, where row i says which of yesterday's buckets end
+    // up in bucket i today.
+    fn transition<T: Count>(&self) -> Matrix<T> {
+        let n = self.buckets;
+        let mut weights = vec![0; n * n];
+        for i in 0..n - 1 {
+            weights[i * n + i + 1] = 1;
+        }
+        weights[self.reset * n] += 1;
+        weights[self.newborn * n] += self.offspring;
+        Matrix { size: n, cells: weights.into_iter().map(T::from_u64).collect() }
+    }
+}
+
+impl School {
+    // The counts after `ticks` days, worked out by raising a day's transition
+    // to that power rather than stepping through them one at a time.
+    pub fn fast_forward<T: Count>(&self, ticks: u64) -> Vec<T> {
+        let counts: Vec<T> = self.counts.iter().map(|&n| T::from_u64(n)).collect();
+        self.lifecycle.transition().pow(ticks).apply(&counts)
+    }
+
+    pub fn fast_forward_census<T: Count>(&self, ticks: u64) -> T {
+        self.fast_forward(ticks).into_iter().fold(T::from_u64(0), |acc, n| acc + n)
     }
 }
 
@@ -60,26 +332,24 @@ impl FromStr for School {
                 Ok(acc)
             });
 
-        let results = results?;
-
-        let pops = results.iter()
-            .try_fold(School(0u64, 0u64, 0u64, 0u64, 0u64, 0u64, 0u64, 0u64, 0u64), |mut acc, cur| {
-                match cur {
-                    0 => acc.0 += 1,
-                    1 => acc.1 += 1,
-                    2 => acc.2 += 1,
-                    3 => acc.3 += 1,
-                    4 => acc.4 += 1,
-                    5 => acc.5 += 1,
-                    6 => acc.6 += 1,
-                    7 => acc.7 += 1,
-                    8 => acc.8 += 1,
-                    _ => return Err("Got an impossible age from the submarine")
-                }
-                Ok(acc)
-            });
+        Ok(School::from_ages(&results?)?)
+    }
+}
 
-        Ok(pops?)
+impl School {
+    // A school of lanternfish.
+    fn from_ages(ages: &[u8]) -> Result<Self, &'static str> {
+        let ages: Vec<usize> = ages.iter().map(|&age| usize::from(age)).collect();
+        School::with_lifecycle(Lifecycle::LANTERNFISH, &ages)
+    }
+
+    pub fn with_lifecycle(lifecycle: Lifecycle, ages: &[usize]) -> Result<Self, &'static str> {
+        ages.iter()
+            .try_fold(School::empty(lifecycle), |mut acc, &age| {
+                let bucket = acc.counts.get_mut(age).ok_or("Got an impossible age from the submarine")?;
+                *bucket += 1;
+                Ok(acc)
+            })
     }
 }
 
@@ -90,34 +360,209 @@ mod function_tests {
 
     #[test]
     fn school_from_string() {
-        assert_eq!(School(0, 0, 3, 2, 0, 0, 0, 0, 1), School::from_str("8,2,3,2,3,2").unwrap());
+        assert_eq!(&[0, 0, 3, 2, 0, 0, 0, 0, 1], School::from_str("8,2,3,2,3,2").unwrap().counts());
     }
 
     #[test]
     fn calculate_total_population() {
-        let school = School(0, 3, 6, 9, 1, 0, 0, 4, 5);
+        let school = School { lifecycle: Lifecycle::LANTERNFISH, counts: vec![0, 3, 6, 9, 1, 0, 0, 4, 5] };
         assert_eq!(28, school.census());
     }
 }
 
 #[cfg(test)]
-mod answer_tests {
+mod lifecycle_tests {
     use super::*;
-    use crate::read_input;
 
     #[test]
-    fn simulate_fish() {
-        let input = read_input("../testinputs/06.txt");
-        if let Answer::U64(fish, _) = lanternfish(input) {
-            assert_eq!(5934, fish);
-        } else { panic!("Answer was not a U64 variant.") }
+    fn lanternfish_days() {
+        // The first few days of the puzzle's example
+        let mut school = School::from_ages(&[3, 4, 3, 1, 2]).unwrap();
+        assert_eq!(&[0, 1, 1, 2, 1, 0, 0, 0, 0], school.counts());
+        school.age();
+        assert_eq!(&[1, 1, 2, 1, 0, 0, 0, 0, 0], school.counts());
+        school.age();
+        assert_eq!(&[1, 2, 1, 0, 0, 0, 1, 0, 1], school.counts());
+    }
+
+    #[test]
+    fn litters_and_short_lives() {
+        // Spawn every third day, three at a time, and newborns are straight
+        // into the same cycle.
+        let lifecycle = Lifecycle::new(3, 2, 2, 3).unwrap();
+        let mut school = School::with_lifecycle(lifecycle, &[0]).unwrap();
+        school.age();
+        assert_eq!(&[0, 0, 4], school.counts());
+        assert_eq!(4 * 4 * 4, school.projected_census(6));
+    }
+
+    #[test]
+    fn extra_buckets_for_late_starters() {
+        let lifecycle = Lifecycle::new(12, 6, 8, 1).unwrap();
+        let school = School::with_lifecycle(lifecycle, &[11]).unwrap();
+        assert_eq!(1, school.projected_census(11));
+        assert_eq!(2, school.projected_census(12));
+        // Nobody ever gets back up to the top buckets
+        let mut school = school;
+        school.time_travel(30);
+        assert_eq!(&[0, 0, 0], &school.counts()[9..]);
+    }
+
+    #[test]
+    fn bad_lifecycles_and_ages() {
+        assert!(Lifecycle::new(6, 6, 4, 1).is_err());
+        assert!(Lifecycle::new(9, 6, 9, 1).is_err());
+        assert!(School::with_lifecycle(Lifecycle::LANTERNFISH, &[9]).is_err());
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use super::*;
+
+    fn example() -> School {
+        School::from_ages(&[3, 4, 3, 1, 2]).unwrap()
+    }
+
+    #[test]
+    fn every_day_is_recorded() {
+        let history = example().history::<u64>(80);
+        assert_eq!(Some(&[0, 1, 1, 2, 1, 0, 0, 0, 0][..]), history.day(0));
+        assert_eq!(Some(&[1, 2, 1, 0, 0, 0, 1, 0, 1][..]), history.day(2));
+        assert_eq!(None, history.day(81));
+        let totals: Vec<u64> = history.totals().collect();
+        assert_eq!(81, totals.len());
+        assert_eq!((5, 26, 5934), (totals[0], totals[18], totals[80]));
+    }
+
+    #[test]
+    fn csv_rows() {
+        let csv = example().history::<u64>(2).to_csv();
+        assert_eq!("\
+day,age_0,age_1,age_2,age_3,age_4,age_5,age_6,age_7,age_8,total
+0,0,1,1,2,1,0,0,0,0,5
+1,1,1,2,1,0,0,0,0,0,5
+2,1,2,1,0,0,0,1,0,1,6
+", csv);
     }
 
     #[test]
-    fn simulate_fish_harder() {
-        let input = read_input("../testinputs/06.txt");
-        if let Answer::U64(_, more_fish) = lanternfish(input) {
-            assert_eq!(26984457539, more_fish);
-        } else { panic!("Answer was not a U64 variant.") }
+    fn first_day_over() {
+        // 22 fish on day 17 and 26 on day 18
+        let school = example();
+        assert_eq!(Some(0), school.first_day_over(4));
+        assert_eq!(Some(18), school.first_day_over(22));
+        assert_eq!(Some(18), school.first_day_over(25));
+        assert_eq!(Some(80), school.first_day_over(5933));
+        assert_eq!(Some(18), school.history::<u64>(20).first_day_over(22));
+        assert_eq!(None, school.history::<u128>(20).first_day_over(5933));
+    }
+
+    #[test]
+    fn bigger_than_a_u64() {
+        let school = example();
+        let day = school.first_day_over(u64::MAX).unwrap();
+        assert!(school.fast_forward_census::<u128>(u64::from(day)) > u128::from(u64::MAX));
+        assert!(school.fast_forward_census::<u128>(u64::from(day) - 1) <= u128::from(u64::MAX));
+
+        // A single day's litter can be what tips it over
+        let litter = Lifecycle::new(1, 0, 0, u64::MAX).unwrap();
+        assert_eq!(Some(1), School::with_lifecycle(litter, &[0]).unwrap().first_day_over(u64::MAX));
+    }
+
+    #[test]
+    fn histories_longer_than_a_u64() {
+        // u64 totals run out around day 490
+        let school = example();
+        let history = school.history::<u128>(600);
+        let totals: Vec<u128> = history.totals().collect();
+        assert_eq!(school.fast_forward_census::<u128>(600), totals[600]);
+        assert!(totals[600] > u128::from(u64::MAX));
+
+        let day = school.first_day_over(u64::MAX);
+        assert_eq!(day, history.first_day_over(u64::MAX));
+
+        let big = school.history::<BigUint>(1200);
+        assert_eq!(Some(&school.fast_forward_census::<BigUint>(1200)), big.totals().last().as_ref());
+        assert!(big.to_csv().lines().nth(601).unwrap().ends_with(&format!(",{}", totals[600])));
+    }
+
+    #[test]
+    fn schools_that_never_grow() {
+        let barren = Lifecycle::new(9, 6, 8, 0).unwrap();
+        assert_eq!(None, School::with_lifecycle(barren, &[3, 4]).unwrap().first_day_over(2));
+        assert_eq!(None, School::empty(Lifecycle::LANTERNFISH).first_day_over(0));
+    }
+
+    #[test]
+    fn renders_the_population() {
+        let renders = Lanternfish::render(&example());
+        assert_eq!(1, renders.len());
+        let (name, rendering) = &renders[0];
+        assert_eq!(("population", "csv"), (*name, rendering.extension()));
+        assert_eq!(258, rendering.contents().lines().count());
+        assert!(rendering.contents().ends_with(",26984457539\n"));
+    }
+}
+
+#[cfg(test)]
+mod projection_tests {
+    use super::*;
+
+    const PRIME: u64 = 1_000_000_007;
+
+    fn example() -> School {
+        School::from_ages(&[3, 4, 3, 1, 2]).unwrap()
+    }
+
+    #[test]
+    fn agrees_with_stepping() {
+        let mut school = example();
+        for day in 0..=256 {
+            assert_eq!(school.counts(), &school.fast_forward::<u64>(0)[..]);
+            assert_eq!(school.counts(), &example().fast_forward::<u64>(day)[..], "day {}", day);
+            school.age();
+        }
+        let lifecycle = Lifecycle::new(5, 2, 4, 3).unwrap();
+        let school = School::with_lifecycle(lifecycle, &[0, 1, 3]).unwrap();
+        assert_eq!(school.projected_census(20), school.fast_forward_census::<u64>(20));
+    }
+
+    #[test]
+    fn every_kind_of_count() {
+        let school = example();
+        assert_eq!(26984457539u64, school.fast_forward_census(256));
+        assert_eq!(26984457539u128, school.fast_forward_census(256));
+        assert_eq!("26984457539", school.fast_forward_census::<BigUint>(256).to_string());
+
+        // Long enough that u64 would have overflowed, but u128 hasn't yet
+        let exact: u128 = school.fast_forward_census(900);
+        assert!(exact > u128::from(u64::MAX));
+        assert_eq!(exact.to_string(), school.fast_forward_census::<BigUint>(900).to_string());
+        assert_eq!(Mod::<PRIME>((exact % u128::from(PRIME)) as u64), school.fast_forward_census(900));
+    }
+
+    #[test]
+    fn a_trillion_days_modulo_a_prime() {
+        let school = example();
+        let eve: Vec<Mod<PRIME>> = school.fast_forward(999_999_999_999);
+        let day = Lifecycle::LANTERNFISH.transition::<Mod<PRIME>>().apply(&eve);
+        assert_eq!(day, school.fast_forward(1_000_000_000_000));
+    }
+}
+
+example_tests! {
+    Lanternfish;
+    example: "06.txt" => 5934, 26984457539u64;
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    #[test]
+    fn impossible_age() {
+        let error = Lanternfish::parse(&Input::from("3,4,9")).unwrap_err();
+        assert_eq!(Some(1), error.line);
     }
 }
@@ -1,80 +1,338 @@
 // Day 7
 
 use crate::Answer;
-use std::cmp::min;
+use crate::error::{Error, Result};
+use crate::input::Input;
+use crate::solver::Solver;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::ops::RangeInclusive;
 
-pub fn crab_target_alignment(input: Vec<String>) -> Answer {
-    let mut crab_positions: Vec<i64> = input.iter().take(1)
-        .flat_map(|x| x.split(','))
-        .map(|x| x.parse().unwrap()).collect();
+pub struct CrabTargetAlignment;
 
-    crab_positions.sort_unstable();
+impl Solver for CrabTargetAlignment {
+    type Parsed = Vec<i64>;
 
-    let optimal_target = crab_positions[crab_positions.len() / 2];
-    let optimal_cost = cost_to_target(optimal_target, &crab_positions);
+    fn parse(input: &Input) -> Result<Self::Parsed> {
+        let mut crab_positions: Vec<i64> = input.first_line("a line of crab positions")?
+            .csv("a crab position")?;
 
-    let num_crabs = crab_positions.len() as f64;
-    let optimal_inc_target = crab_positions.iter().map(|p| *p as f64).sum::<f64>() / num_crabs;
+        crab_positions.sort_unstable();
+        Ok(crab_positions)
+    }
+
+    fn part1(crab_positions: &Self::Parsed) -> Result<Answer> {
+        cheapest_cost(crab_positions, flat)
+    }
+
+    fn part2(crab_positions: &Self::Parsed) -> Result<Answer> {
+        cheapest_cost(crab_positions, increasing)
+    }
+}
+
+fn cheapest_cost(crab_positions: &[i64], fuel: impl Fn(u64) -> u128) -> Result<Answer> {
+    let alignment = align(&crabs(crab_positions), fuel, Search::default())?;
+    Ok(Answer::U128(alignment.cost))
+}
+
+// What a journey of `distance` steps costs, for a few ways of pricing each
+// step, added up in closed form so a long journey costs no more to work out
+// than a short one. Anything else will do too, as long as no step is cheaper
+// than the one before, since that's what keeps the total cost convex.
+pub fn flat(distance: u64) -> u128 {
+    u128::from(distance)
+}
 
-    let (opt_floor, opt_ceil) = (optimal_inc_target.floor() as i64, optimal_inc_target.ceil() as i64);
+// The nth step costs n.
+pub fn increasing(distance: u64) -> u128 {
+    let distance = u128::from(distance);
+    distance * (distance + 1) / 2
+}
 
-    let opt_inc_cost = [opt_floor, opt_ceil].iter()
-        .map(|x| increasing_cost_to_target(*x, &crab_positions))
-        .min().unwrap();
+// The nth step costs 2n - 1, so going `d` steps costs d squared.
+pub fn quadratic(distance: u64) -> u128 {
+    u128::from(distance) * u128::from(distance)
+}
 
-    Answer::I64(optimal_cost, opt_inc_cost)
+// Steps get dearer like `increasing` until they cost `cap`, then stay there.
+pub fn capped(cap: u64) -> impl Fn(u64) -> u128 {
+    move |distance| {
+        let ramp = distance.min(cap);
+        increasing(ramp) + u128::from(distance - ramp) * u128::from(cap)
+    }
 }
 
-fn increasing_cost_to_target(target: i64, set: &[i64]) -> i64 {
-    set.iter().map(|x| (target - *x).abs()).map(|n| (n * (n + 1)) / 2).sum()
+// Any per-step cost, added up a step at a time. That's a call per step for
+// every crab at every target we try, so it's only for crabs close together.
+pub fn per_step(step_cost: impl Fn(u64) -> u128) -> impl Fn(u64) -> u128 {
+    move |distance| (1..=distance).map(&step_cost).sum()
 }
 
-fn cost_to_target(target: i64, set: &[i64]) -> i64 {
-    set.iter().map(|x| (target - *x).abs()).sum()
+// Each crab's fuel bill gets multiplied by its weight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Crab {
+    pub position: i64,
+    pub weight: u64,
+}
+
+fn crabs(positions: &[i64]) -> Vec<Crab> {
+    positions.iter().map(|&position| Crab { position, weight: 1 }).collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Search {
+    #[default]
+    Ternary,
+    GoldenSection,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alignment {
+    // Every target that's as cheap as it gets. The cheapest targets of a
+    // convex cost are always next to each other.
+    pub targets: RangeInclusive<i64>,
+    pub cost: u128,
+}
+
+// The cheapest places for all the crabs to meet. Moving past the outermost
+// crab only makes every journey longer, so the search stays between them,
+// and only ever costs the handful of targets it probes.
+pub fn align(crabs: &[Crab], fuel: impl Fn(u64) -> u128, search: Search) -> Result<Alignment> {
+    let lo = crabs.iter().map(|crab| crab.position).min()
+        .ok_or_else(|| Error::input("at least one crab"))?;
+    let hi = crabs.iter().map(|crab| crab.position).max().unwrap();
+    let span = hi.checked_sub(lo)
+        .ok_or_else(|| Error::input(format!("crabs no more than {} apart", i64::MAX)))? as u64;
+
+    // Targets are searched for by how far they are from the leftmost crab.
+    // A bill too big to count doesn't wrap, it spoils the whole answer.
+    let overflowed = Cell::new(false);
+    let target = |offset: u64| lo + offset as i64;
+    let cost = |offset: u64| -> u128 {
+        cost_to_target(crabs, target(offset), &fuel).unwrap_or_else(|| {
+            overflowed.set(true);
+            u128::MAX
+        })
+    };
+
+    let best = match search {
+        Search::Ternary => ternary_search(span, cost),
+        Search::GoldenSection => golden_section_search(span, cost),
+    };
+    let least = cost(best);
+
+    // The cost falls to the cheapest stretch and climbs after it, so binary
+    // search each side for where it stops being the least.
+    let first = partition_point(0, best, |offset| cost(offset) > least);
+    let last = partition_point(best, span + 1, |offset| cost(offset) == least) - 1;
+
+    if overflowed.get() {
+        return Err(Error::input("fuel bills that fit in a u128"));
+    }
+    Ok(Alignment { targets: target(first)..=target(last), cost: least })
+}
+
+// What moving every crab to `target` costs, or None if it's too much to count.
+fn cost_to_target(crabs: &[Crab], target: i64, fuel: impl Fn(u64) -> u128) -> Option<u128> {
+    crabs.iter().try_fold(0u128, |acc, crab| {
+        fuel(target.abs_diff(crab.position))
+            .checked_mul(u128::from(crab.weight))?
+            .checked_add(acc)
+    })
+}
+
+// The first offset in lo..hi that fails `pred`, which has to hold for a run
+// at the start of the range and then never again.
+fn partition_point(mut lo: u64, mut hi: u64, pred: impl Fn(u64) -> bool) -> u64 {
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+// Some offset in 0..=span with the least cost. With a convex cost, whichever
+// third point costs more can't be nearer the bottom than the other one, and
+// if they tie the bottom is between them.
+fn ternary_search(span: u64, cost: impl Fn(u64) -> u128) -> u64 {
+    let (mut lo, mut hi) = (0, span);
+    while hi - lo >= 3 {
+        let (m1, m2) = (lo + (hi - lo) / 3, hi - (hi - lo) / 3);
+        match cost(m1).cmp(&cost(m2)) {
+            Ordering::Less => hi = m2 - 1,
+            Ordering::Greater => lo = m1 + 1,
+            Ordering::Equal => (lo, hi) = (m1, m2),
+        }
+    }
+    (lo..=hi).min_by_key(|&offset| cost(offset)).unwrap()
+}
+
+// The same idea, but with the probes at Fibonacci splits so each round
+// reuses one of the last round's costs. The window can poke out past `span`,
+// where everything counts as dearer than anything inside.
+fn golden_section_search(span: u64, cost: impl Fn(u64) -> u128) -> u64 {
+    let cost = |offset: u64| if offset > span { None } else { Some(cost(offset)) };
+    let dearer = |a: Option<u128>, b: Option<u128>| match (a, b) {
+        (Some(a), Some(b)) => a > b,
+        (a, b) => a.is_none() && b.is_some(),
+    };
+
+    let mut fib: Vec<u64> = vec![1, 1];
+    while fib[fib.len() - 1] < span {
+        fib.push(fib[fib.len() - 1] + fib[fib.len() - 2]);
+    }
+
+    // The window is start..=start + fib[k], probed at fib[k - 2] and fib[k - 1] in.
+    let mut start = 0;
+    let mut k = fib.len() - 1;
+    while k >= 3 {
+        let (x1, x2) = (start + fib[k - 2], start + fib[k - 1]);
+        if dearer(cost(x1), cost(x2)) {
+            start = x1;
+        }
+        k -= 1;
+    }
+    (start..=(start + fib[k]).min(span)).min_by_key(|&offset| cost(offset).unwrap()).unwrap()
 }
 
 #[cfg(test)]
 mod cost_to_target_tests {
-    use super::cost_to_target;
+    use super::*;
+
+    const SET: [i64; 7] = [3, 4, 4, 5, 7, 8, 9];
 
     #[test]
     fn interior_target() {
-        let set = vec![3, 4, 4, 5, 7, 8, 9];
-        assert_eq!(14, cost_to_target(6, &set));
+        assert_eq!(Some(14), cost_to_target(&crabs(&SET), 6, flat));
     }
 
     #[test]
     fn below_target() {
-        let set = vec![3, 4, 4, 5, 7, 8, 9];
-        assert_eq!(33, cost_to_target(1, &set));
+        assert_eq!(Some(33), cost_to_target(&crabs(&SET), 1, flat));
     }
 
     #[test]
     fn above_target() {
-        let set = vec![3, 4, 4, 5, 7, 8, 9];
-        assert_eq!(72, cost_to_target(16, &set));
+        assert_eq!(Some(72), cost_to_target(&crabs(&SET), 16, flat));
     }
 }
 
 #[cfg(test)]
-mod answer_tests {
+mod alignment_tests {
     use super::*;
+    use crate::testing::Lcg;
+
+    const EXAMPLE: [i64; 10] = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+    const SEARCHES: [Search; 2] = [Search::Ternary, Search::GoldenSection];
+
+    fn brute_force(crabs: &[Crab], fuel: impl Fn(u64) -> u128) -> Alignment {
+        let costs: Vec<(i64, u128)> = (-5..=50)
+            .map(|target: i64| {
+                let cost = crabs.iter().map(|c| u128::from(c.weight) * fuel(target.abs_diff(c.position))).sum();
+                (target, cost)
+            })
+            .collect();
+        let cost = costs.iter().map(|&(_, cost)| cost).min().unwrap();
+        let targets: Vec<i64> = costs.iter().filter(|&&(_, c)| c == cost).map(|&(t, _)| t).collect();
+        Alignment { targets: targets[0]..=*targets.last().unwrap(), cost }
+    }
+
+    #[test]
+    fn example_targets() {
+        for search in SEARCHES {
+            assert_eq!(Ok(Alignment { targets: 2..=2, cost: 37 }), align(&crabs(&EXAMPLE), flat, search));
+            assert_eq!(Ok(Alignment { targets: 5..=5, cost: 168 }), align(&crabs(&EXAMPLE), increasing, search));
+        }
+    }
+
+    #[test]
+    fn closed_forms_add_up_the_steps() {
+        for distance in 0..50 {
+            assert_eq!(per_step(|_| 1)(distance), flat(distance));
+            assert_eq!(per_step(u128::from)(distance), increasing(distance));
+            assert_eq!(per_step(|n| u128::from(2 * n - 1))(distance), quadratic(distance));
+            assert_eq!(per_step(|n| u128::from(n.min(4)))(distance), capped(4)(distance));
+        }
+    }
+
+    #[test]
+    fn ties_give_a_range() {
+        for search in SEARCHES {
+            assert_eq!(Ok(Alignment { targets: 1..=3, cost: 2 }), align(&crabs(&[1, 3]), flat, search));
+            assert_eq!(Ok(Alignment { targets: 0..=1, cost: 1 }), align(&crabs(&[0, 1]), quadratic, search));
+            assert_eq!(Ok(Alignment { targets: 4..=4, cost: 0 }), align(&crabs(&[4, 4]), quadratic, search));
+        }
+    }
 
-    use crate::read_input;
+    #[test]
+    fn weights_pull_the_target() {
+        let crabs = [Crab { position: 0, weight: 3 }, Crab { position: 10, weight: 1 }];
+        assert_eq!(Ok(Alignment { targets: 0..=0, cost: 10 }), align(&crabs, flat, Search::default()));
+        // Squared distances meet at the weighted mean
+        assert_eq!(Ok(Alignment { targets: 2..=3, cost: 3 * 4 + 64 }), align(&crabs, quadratic, Search::default()));
+    }
 
     #[test]
-    fn minimum_fuel() {
-        let input = read_input("../testinputs/07.txt");
-        if let Answer::I64(fuel, _) = crab_target_alignment(input) {
-            assert_eq!(37, fuel);
-        } else { panic!("Answer was not a I64 variant.") }
+    fn widely_spaced_crabs() {
+        let far = crabs(&[0, 5_000_000_000]);
+        for search in SEARCHES {
+            assert_eq!(Ok(Alignment { targets: 0..=5_000_000_000, cost: 5_000_000_000 }), align(&far, flat, search));
+            let cost = 2 * increasing(2_500_000_000);
+            assert_eq!(Ok(Alignment { targets: 2_500_000_000..=2_500_000_000, cost }), align(&far, increasing, search));
+        }
+        let answers = crate::solver::run::<CrabTargetAlignment>(&Input::from("0,5000000000"), None).unwrap();
+        let answers: Vec<String> = answers.into_iter().map(|(_, answer)| answer.unwrap().to_string()).collect();
+        assert_eq!(vec!["5000000000", "6250000002500000000"], answers);
+
+        // As far apart as an i64 allows
+        let edges = crabs(&[i64::MIN, -1]);
+        assert_eq!(Ok(i64::MIN..=-1), align(&edges, flat, Search::default()).map(|a| a.targets));
     }
 
     #[test]
-    fn minimum_increasing_fuel() {
-        let input = read_input("../testinputs/07.txt");
-        if let Answer::I64(_, fuel) = crab_target_alignment(input) {
-            assert_eq!(168, fuel);
-        } else { panic!("Answer was not a I64 variant.") }
+    fn impossible_alignments() {
+        assert!(align(&[], flat, Search::default()).is_err());
+        assert!(align(&crabs(&[i64::MIN, 0]), flat, Search::default()).is_err());
+        assert!(align(&crabs(&[i64::MIN, i64::MAX]), flat, Search::default()).is_err());
+        let heavy = [Crab { position: 0, weight: u64::MAX }, Crab { position: i64::MAX, weight: u64::MAX }];
+        assert!(align(&heavy, quadratic, Search::default()).is_err());
+    }
+
+    #[test]
+    fn agrees_with_brute_force() {
+        let mut rng = Lcg::new(7);
+        let mut next = |n: u64| rng.below(n);
+        for _ in 0..200 {
+            let crabs: Vec<Crab> = (0..=next(8))
+                .map(|_| Crab { position: next(40) as i64, weight: next(4) + 1 })
+                .collect();
+            let cap = next(6) + 1;
+            for search in SEARCHES {
+                assert_eq!(Ok(brute_force(&crabs, flat)), align(&crabs, flat, search), "{:?}", crabs);
+                assert_eq!(Ok(brute_force(&crabs, increasing)), align(&crabs, increasing, search), "{:?}", crabs);
+                assert_eq!(Ok(brute_force(&crabs, quadratic)), align(&crabs, quadratic, search), "{:?}", crabs);
+                assert_eq!(Ok(brute_force(&crabs, capped(cap))), align(&crabs, capped(cap), search), "{:?} capped at {}", crabs, cap);
+            }
+        }
+    }
+}
+
+example_tests! {
+    CrabTargetAlignment;
+    example: "07.txt" => 37, 168;
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    #[test]
+    fn bad_position() {
+        let error = CrabTargetAlignment::parse(&Input::from("16,1,2x")).unwrap_err();
+        assert_eq!((Some(1), Some(6)), (error.line, error.column));
     }
 }
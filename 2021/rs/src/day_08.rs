@@ -1,38 +1,54 @@
 // Day 8
 
 use crate::Answer;
-use std::str::FromStr;
-
-pub fn seven_seg_decode(input: Vec<String>) -> Answer {
-    let mut displays: Vec<Display> = input.iter()
-        .map(|s| Display::from_str(s).unwrap()).collect();
-
-    let simple_outputs = displays.iter()
-        .map(|d| {
-            d.values.iter()
-                .filter(|v| {
-                    match v.cardinality() {
-                        2 | 3 | 4 | 7 => true,
-                        _ => false
-                    }
-                })
-                .count()
-        }).sum::<usize>().try_into().unwrap();
-
-    let full_monty = displays.iter_mut()
-        .map(|d| {
-            d.decode_patterns();
-            d.print_value()
-        })
-        .sum();
+use crate::error::{Error, Result};
+use crate::input::{Input, Line};
+use crate::solver::Solver;
+
+pub struct SevenSegDecode;
+
+impl Solver for SevenSegDecode {
+    type Parsed = Vec<Display>;
+
+    fn parse(input: &Input) -> Result<Self::Parsed> {
+        input.lines()
+            .map(|line| Display::parse(&line))
+            .collect()
+    }
+
+    fn part1(displays: &Self::Parsed) -> Result<Answer> {
+        let simple_outputs = displays.iter()
+            .map(|d| {
+                d.values.iter()
+                    .filter(|v| {
+                        matches!(v.cardinality(), 2 | 3 | 4 | 7)
+                    })
+                    .count()
+            }).sum::<usize>().try_into().unwrap();
 
-    Answer::U32(simple_outputs, full_monty)
+        Ok(Answer::U32(simple_outputs))
+    }
+
+    fn part2(displays: &Self::Parsed) -> Result<Answer> {
+        // One display per line, so the index doubles as the line number
+        let full_monty = displays.iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, mut d)| {
+                d.decode_patterns()
+                    .and_then(|_| d.print_value())
+                    .map_err(|e| e.on_line(i + 1))
+            })
+            .sum::<Result<u32>>()?;
+
+        Ok(Answer::U32(full_monty))
+    }
 }
 
 use std::collections::BTreeMap;
 
-#[derive(Debug)]
-struct Display {
+#[derive(Clone, Debug)]
+pub struct Display {
     patterns: [Pattern; 10],
     values: [Pattern; 4],
     index: BTreeMap<u8, u8>,
@@ -51,39 +67,50 @@ impl Display {
         self.index.insert(pattern, value);
     }
 
-    fn get_value(&self, pattern: u8) -> u8 {
-        *self.index.get(&pattern).unwrap()
+    fn get_value(&self, pattern: u8) -> Result<u8> {
+        self.index.get(&pattern).copied()
+            .ok_or_else(|| Error::input("output values that appear among the patterns"))
     }
 
-    fn decode_patterns(&mut self) {
+    // Work out which pattern is which digit. Every step relies on the one
+    // before, so a single unrecognisable pattern means the display is bad.
+    fn decode_patterns(&mut self) -> Result<()> {
+        let undecodable = |digit: u8| {
+            Error::input(format!("a pattern that decodes to {}", digit))
+        };
+
         let one = self.patterns.into_iter()
-            .find(|p| p.cardinality() == 2).unwrap();
+            .find(|p| p.cardinality() == 2)
+            .ok_or_else(|| undecodable(1))?;
         self.insert(one.bits(), 1);
 
         let seven = self.patterns.into_iter()
-            .find(|p| p.cardinality() == 3).unwrap();
+            .find(|p| p.cardinality() == 3)
+            .ok_or_else(|| undecodable(7))?;
         self.insert(seven.bits(), 7);
 
         let four = self.patterns.into_iter()
-            .find(|p| p.cardinality() == 4).unwrap();
+            .find(|p| p.cardinality() == 4)
+            .ok_or_else(|| undecodable(4))?;
         self.insert(four.bits(), 4);
 
         let eight = self.patterns.into_iter()
-            .find(|p| p.cardinality() == 7).unwrap();
+            .find(|p| p.cardinality() == 7)
+            .ok_or_else(|| undecodable(8))?;
         self.insert(eight.bits(), 8);
 
         let three = self.patterns.into_iter()
             .find(|p| {
                 p.cardinality() == 5
                     && p.bits() == p.bits() | one.bits()
-            }).unwrap();
+            }).ok_or_else(|| undecodable(3))?;
         self.insert(three.bits(), 3);
 
         let nine = self.patterns.into_iter()
             .find(|p| {
                 p.cardinality() == 6
                     && p.bits() == p.bits() | four.bits()
-            }).unwrap();
+            }).ok_or_else(|| undecodable(9))?;
         self.insert(nine.bits(), 9);
 
         let zero = self.patterns.into_iter()
@@ -91,7 +118,7 @@ impl Display {
                 p.cardinality() == 6
                     && p.bits() != nine.bits()
                     && p.bits() == p.bits() | one.bits()
-            }).unwrap();
+            }).ok_or_else(|| undecodable(0))?;
         self.insert(zero.bits(), 0);
 
         let six = self.patterns.into_iter()
@@ -99,14 +126,14 @@ impl Display {
                 p.cardinality() == 6
                     && p.bits() != nine.bits()
                     && p.bits() != zero.bits()
-            }).unwrap();
+            }).ok_or_else(|| undecodable(6))?;
         self.insert(six.bits(), 6);
 
         let two = self.patterns.into_iter()
             .find(|p| {
                 p.cardinality() == 5
                     && Pattern::new(p.bits() | nine.bits()).cardinality() == 7
-            }).unwrap();
+            }).ok_or_else(|| undecodable(2))?;
         self.insert(two.bits(), 2);
 
         let five = self.patterns.into_iter()
@@ -114,16 +141,18 @@ impl Display {
                 p.cardinality() == 5 
                     && p.bits() != two.bits()
                     && p.bits() != three.bits()
-            }).unwrap();
+            }).ok_or_else(|| undecodable(5))?;
         self.insert(five.bits(), 5);
+
+        Ok(())
     }
 
-    fn print_value(&self) -> u32 {
+    fn print_value(&self) -> Result<u32> {
         self.values.into_iter().enumerate()
-            .fold(0, |acc, (i, p)| {
-                let value = self.get_value(p.bits()) as u32;
+            .try_fold(0, |acc, (i, p)| {
+                let value = self.get_value(p.bits())? as u32;
                 let i: u32 = i.try_into().unwrap();
-                acc + 10u32.pow(3 - i) * value
+                Ok(acc + 10u32.pow(3 - i) * value)
             })
     }
 }
@@ -174,50 +203,66 @@ mod pattern_tests {
     }
 }
 
-impl FromStr for Display {
-    type Err = std::string::ParseError;
-    fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let patterns = input
-            .replace(" | ", " ").split_whitespace()
-            .map(|pattern| {
-                pattern.chars()
-                    .map(|c| (c as u32) - 97 )
-                    .fold(Pattern([false; 7]), |mut acc, i| {
-                        acc.0[i as usize] = true;
-                        acc
-                    })
-            })
-            .collect::<Vec<Pattern>>();
+impl Display {
+    fn parse(line: &Line) -> Result<Self> {
+        let (patterns, values) = line.split_once(" | ", "ten patterns, ' | ' and four output values")?;
+
+        let parse_patterns = |section: &str| {
+            section.split_whitespace()
+                .map(|pattern| parse_pattern(line, pattern))
+                .collect::<Result<Vec<Pattern>>>()
+        };
+        let (patterns, values) = (parse_patterns(patterns)?, parse_patterns(values)?);
 
         Ok(Display::new(
-                patterns[..10].try_into().unwrap(),
-                patterns[10..].try_into().unwrap()
+            patterns.try_into().map_err(|p: Vec<_>| {
+                line.error("ten patterns before ' | '").found(format!("{} patterns", p.len()))
+            })?,
+            values.try_into().map_err(|v: Vec<_>| {
+                line.error("four output values after ' | '").found(format!("{} values", v.len()))
+            })?,
         ))
     }
 }
 
+// Segments are the letters a-g; anything else in the line gets pointed at.
+fn parse_pattern(line: &Line, pattern: &str) -> Result<Pattern> {
+    pattern.char_indices()
+        .try_fold(Pattern([false; 7]), |mut acc, (offset, c)| {
+            if !('a'..='g').contains(&c) {
+                let segment = &pattern[offset..offset + c.len_utf8()];
+                return Err(line.error_at(segment, "a segment letter from a to g"));
+            }
+            acc.0[(c as u32 - 97) as usize] = true;
+            Ok(acc)
+        })
+}
+
 #[cfg(test)]
-mod answer_tests {
+mod parse_tests {
     use super::*;
     use crate::read_input;
 
     #[test]
-    fn simple_digits() {
+    fn bad_segment() {
         let input = read_input("../testinputs/08.txt");
-        if let Answer::U32(count, _) = seven_seg_decode(input) {
-            assert_eq!(26, count);
-        } else {
-            panic!("wrong type");
-        }
+        let input = Input::new(input.text().replacen("fgaebd cg", "fgaebd cx", 1));
+        let error = SevenSegDecode::parse(&input).unwrap_err();
+        assert_eq!((Some(3), Some(9)), (error.line, error.column));
     }
 
     #[test]
-    fn display_values() {
-        let input = read_input("../testinputs/08.txt");
-        if let Answer::U32(_, sum) = seven_seg_decode(input) {
-            assert_eq!(61229, sum);
-        } else {
-            panic!("wrong type");
-        }
+    fn missing_output_values() {
+        let input = Input::from("be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe");
+        let error = SevenSegDecode::parse(&input).unwrap_err();
+        assert_eq!(Some(1), error.line);
+    }
+
+    #[test]
+    fn undecodable_display() {
+        // Every pattern is the same one, so most digits can't be found
+        let input = Input::new(format!("{}| ab ab ab ab", "ab ".repeat(10)));
+        let input = SevenSegDecode::parse(&input).unwrap();
+        assert_eq!(Some(1), SevenSegDecode::part2(&input).unwrap_err().line);
     }
 }
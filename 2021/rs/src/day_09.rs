@@ -1,89 +1,321 @@
 // Day 9
 
 use crate::Answer;
+use crate::error::{Error, Result};
+use crate::grid::{Connectivity, Grid, Pos};
+use crate::input::Input;
+use crate::netpbm::{self, Image};
+use crate::solver::{Renders, Solver};
 
-pub fn tube_smoke(input: Vec<String>) -> Answer {
-    let heightmap: Vec<Vec<u32>> = input.iter()
-        .map(|s| {
-            s.chars().into_iter()
-                .map(|c| {
-                    c.to_digit(10).unwrap()
-                })
-                .collect()
-        })
-        .collect();
-
-    let mut low_point_risks: Vec<(u32, (u32, u32))> = Vec::new();
-
-    for (i, row) in heightmap.iter().enumerate() {
-        for (j, height) in row.iter().enumerate() {
-            if is_low_point(j, i, *height, &heightmap) {
-                low_point_risks.push((height + 1, (j as u32, i as u32)));
-            }
+pub struct TubeSmoke;
+
+impl Solver for TubeSmoke {
+    type Parsed = Grid<u8>;
+
+    fn parse(input: &Input) -> Result<Self::Parsed> {
+        Grid::from_digits(input)
+    }
+
+    fn part1(heightmap: &Self::Parsed) -> Result<Answer> {
+        let total_risk = low_regions(heightmap, LowPointOptions::default()).iter()
+            .map(LowRegion::risk)
+            .sum();
+
+        Ok(Answer::U32(total_risk))
+    }
+
+    fn part2(heightmap: &Self::Parsed) -> Result<Answer> {
+        let basins = Basins::map(heightmap, LowPointOptions::default());
+
+        let largest_basin_product = basins.largest(3).into_iter()
+            .reduce(|acc, cur| acc * cur)
+            .ok_or_else(|| Error::input("a heightmap with at least one low point"))?;
+
+        Ok(Answer::U32(largest_basin_product))
+    }
+
+    fn render(heightmap: &Self::Parsed) -> Renders {
+        let basins = Basins::map(heightmap, LowPointOptions::default());
+        vec![
+            ("heightmap", Image::Gray { pixels: heightmap.clone(), max: 9 }.into()),
+            ("basins", Image::Color(basins.picture(heightmap)).into()),
+        ]
+    }
+}
+
+// The puzzle only compares the four neighbours and wants a strict low point,
+// but heightmaps from elsewhere can have flat bottoms that should still count.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LowPointOptions {
+    pub connectivity: Connectivity,
+    // Treat a connected patch of equal heights as one low region when
+    // everything around it is higher.
+    pub plateaus: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LowRegion {
+    pub height: u8,
+    // In reading order, so the first one is where the region starts.
+    pub cells: Vec<Pos>,
+}
+
+impl LowRegion {
+    // A flat region is still only one low point as far as risk goes.
+    pub fn risk(&self) -> u32 {
+        u32::from(self.height) + 1
+    }
+}
+
+pub fn low_regions(heightmap: &Grid<u8>, options: LowPointOptions) -> Vec<LowRegion> {
+    let mut seen = heightmap.map(|_| false);
+    let mut regions = Vec::new();
+
+    for (pos, &height) in heightmap.iter() {
+        if seen[pos] {
+            continue;
         }
+
+        let cells = if options.plateaus {
+            plateau(heightmap, pos, options.connectivity, &mut seen)
+        } else {
+            seen[pos] = true;
+            vec![pos]
+        };
+
+        // Equal neighbours are already part of a plateau, so anything left
+        // around it just has to be no lower. Without plateaus, a tie rules
+        // the point out.
+        let is_low = cells.iter().all(|&cell| {
+            heightmap.neighbours(cell, options.connectivity).all(|neighbour| {
+                let other = heightmap[neighbour];
+                other > height || (options.plateaus && other == height)
+            })
+        });
+        if is_low {
+            regions.push(LowRegion { height, cells });
+        }
+    }
+
+    regions
+}
+
+// Every cell reachable from `start` without changing height, marking them
+// all as seen. A plateau is either seen all at once or not at all, so
+// sharing one grid with the caller stops anything being flooded twice.
+fn plateau(heightmap: &Grid<u8>, start: Pos, connectivity: Connectivity, seen: &mut Grid<bool>) -> Vec<Pos> {
+    let height = heightmap[start];
+    let mut cells = Vec::new();
+    let mut to_visit = vec![start];
+    while let Some(pos) = to_visit.pop() {
+        if heightmap[pos] != height || seen[pos] {
+            continue;
+        }
+        seen[pos] = true;
+        cells.push(pos);
+        to_visit.extend(heightmap.neighbours(pos, connectivity));
     }
 
-    let total_risk = low_point_risks.iter()
-        .map(|x| x.0)
-        .sum();
+    cells.sort_by_key(|&(x, y)| (y, x));
+    cells
+}
 
-    let mut basin_sizes: Vec<u32> = low_point_risks.iter()
-        .map(|x| get_basin_size(x.1, &heightmap))
-        .collect();
+// Every basin on the map, numbered in the order their low points turn up
+// reading row by row.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Basins {
+    pub low_points: Vec<Pos>,
+    pub sizes: Vec<u32>,
+    // The basin each cell drains into; None for the 9s between them.
+    pub labels: Grid<Option<usize>>,
+}
 
-    basin_sizes.sort_unstable();
+impl Basins {
+    // Flood outwards from each low region until we hit 9s or the edge.
+    pub fn map(heightmap: &Grid<u8>, options: LowPointOptions) -> Self {
+        let regions = low_regions(heightmap, options);
+        let mut labels = heightmap.map(|_| None);
+        let mut sizes = Vec::with_capacity(regions.len());
 
-    let largest_basin_product = basin_sizes.into_iter()
-        .rev().take(3).reduce(|acc, cur| acc * cur).unwrap();
+        for (basin, region) in regions.iter().enumerate() {
+            let mut size = 0;
+            let mut to_visit = region.cells.clone();
+            while let Some(pos) = to_visit.pop() {
+                if heightmap[pos] == 9 || labels[pos].is_some() {
+                    continue;
+                }
+                labels[pos] = Some(basin);
+                size += 1;
+                to_visit.extend(heightmap.neighbours(pos, options.connectivity));
+            }
+            sizes.push(size);
+        }
 
-    Answer::U32(total_risk, largest_basin_product)
+        let low_points = regions.iter().map(|region| region.cells[0]).collect();
+        Basins { low_points, sizes, labels }
+    }
+
+    pub fn basin_at(&self, pos: Pos) -> Option<usize> {
+        self.labels.get(pos).copied().flatten()
+    }
+
+    // Each basin in its own colour, darker the higher up it is, with the 9s
+    // black and the low points picked out in white.
+    pub fn picture(&self, heightmap: &Grid<u8>) -> Grid<netpbm::Rgb> {
+        let mut picture = heightmap.map(|_| [0, 0, 0]);
+        for (pos, basin) in self.labels.iter() {
+            if let Some(basin) = basin {
+                let depth = 1.0 - f64::from(heightmap[pos]) / 10.0;
+                picture[pos] = netpbm::shade(netpbm::palette(*basin), depth);
+            }
+        }
+        for &low_point in &self.low_points {
+            picture[low_point] = [255, 255, 255];
+        }
+        picture
+    }
+
+    // Sizes of the `n` biggest basins, biggest first.
+    pub fn largest(&self, n: usize) -> Vec<u32> {
+        let mut sizes = self.sizes.clone();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes.truncate(n);
+        sizes
+    }
 }
 
-fn get_basin_size(coords: (u32, u32), map: &Vec<Vec<u32>>) -> u32 {
-   2 
+example_tests! {
+    TubeSmoke;
+    example: "09.txt" => 15, 1134;
 }
 
-fn is_low_point(x: usize, y: usize, height: u32, map: &Vec<Vec<u32>>) -> bool {
-    let x: i32 = x.try_into().unwrap();
-    let y: i32 = y.try_into().unwrap();
-    let x_bound: i32 = map[0].len().try_into().unwrap();
-    let y_bound: i32 = map.len().try_into().unwrap();
-    let check_coords: Vec<(i32, i32)>
-        = vec![(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)].into_iter()
-        .filter(|(x, y)| {
-            !(*x == -1 || *x == x_bound || *y == -1 || *y == y_bound)
-        })
-        .collect();
-    let check_heights: Vec<u32> = check_coords.iter()
-        .map(|(x, y)| {
-            map[*y as usize][*x as usize]
-        })
-        .collect();
-    height < *check_heights.iter().min().unwrap()
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    #[test]
+    fn bad_height() {
+        let error = TubeSmoke::parse(&Input::from("2199943210\n39878949a1")).unwrap_err();
+        assert_eq!((Some(2), Some(9)), (error.line, error.column));
+    }
+
+    #[test]
+    fn ragged_rows() {
+        let error = TubeSmoke::parse(&Input::from("2199943210\n398789492")).unwrap_err();
+        assert_eq!(Some(2), error.line);
+    }
 }
 
 #[cfg(test)]
-mod answer_tests {
+mod basin_tests {
     use super::*;
-    use crate::read_input;
+
+    fn example() -> Grid<u8> {
+        TubeSmoke::parse(&crate::read_input("../testinputs/09.txt")).unwrap()
+    }
 
     #[test]
-    fn first_puzzle() {
-        let input = read_input("../testinputs/09.txt");
-        if let Answer::U32(risk, _) = tube_smoke(input) {
-            assert_eq!(15, risk);
-        } else {
-            panic!("wrong type");
-        }
+    fn basin_sizes() {
+        let basins = Basins::map(&example(), LowPointOptions::default());
+        assert_eq!(vec![(1, 0), (9, 0), (2, 2), (6, 4)], basins.low_points);
+        assert_eq!(vec![3, 9, 14, 9], basins.sizes);
+        assert_eq!(vec![14, 9, 9], basins.largest(3));
     }
 
     #[test]
-    fn second_puzzle() {
-        let input = read_input("../testinputs/09.txt");
-        if let Answer::U32(_, second) = tube_smoke(input) {
-            assert_eq!(1134, second);
-        } else {
-            panic!("wrong type");
-        }
+    fn labelled_map() {
+        let basins = Basins::map(&example(), LowPointOptions::default());
+        assert_eq!(Some(0), basins.basin_at((0, 0)));
+        assert_eq!(None, basins.basin_at((2, 0)));
+        assert_eq!(Some(1), basins.basin_at((5, 0)));
+        assert_eq!(Some(3), basins.basin_at((9, 4)));
+        assert_eq!(None, basins.basin_at((10, 0)));
+        assert_eq!(basins.sizes[2] as usize, basins.labels.as_slice().iter().filter(|&&b| b == Some(2)).count());
+    }
+
+    #[test]
+    fn basin_picture() {
+        let heightmap = example();
+        let picture = Basins::map(&heightmap, LowPointOptions::default()).picture(&heightmap);
+        assert_eq!([255, 255, 255], picture[(1, 0)]);
+        assert_eq!([0, 0, 0], picture[(2, 0)]);
+        assert_eq!(netpbm::shade(netpbm::palette(0), 0.8), picture[(0, 0)]);
+        assert_ne!(picture[(0, 0)], picture[(5, 0)]);
+    }
+
+    #[test]
+    fn walls_split_basins() {
+        let heightmap = TubeSmoke::parse(&Input::from("1912\n0910")).unwrap();
+        let basins = Basins::map(&heightmap, LowPointOptions::default());
+        assert_eq!(vec![2, 4], basins.sizes);
+    }
+}
+
+#[cfg(test)]
+mod low_point_tests {
+    use super::*;
+
+    fn heightmap(text: &str) -> Grid<u8> {
+        TubeSmoke::parse(&Input::from(text)).unwrap()
+    }
+
+    fn low_points(text: &str, options: LowPointOptions) -> Vec<Pos> {
+        low_regions(&heightmap(text), options).iter()
+            .map(|region| region.cells[0])
+            .collect()
+    }
+
+    const EIGHT: LowPointOptions = LowPointOptions { connectivity: Connectivity::Eight, plateaus: false };
+    const PLATEAUS: LowPointOptions = LowPointOptions { connectivity: Connectivity::Four, plateaus: true };
+
+    #[test]
+    fn degenerate_maps() {
+        let options = LowPointOptions::default();
+        assert_eq!(vec![(0, 0)], low_points("5", options));
+        assert_eq!(vec![(1, 0), (3, 0)], low_points("3142", options));
+        assert_eq!(vec![(0, 0), (0, 2)], low_points("3\n4\n1\n2", options));
+        assert_eq!(Vec::<Pos>::new(), low_points("55", options));
+    }
+
+    #[test]
+    fn diagonals_can_rule_out_a_low_point() {
+        let text = "52\n15";
+        assert_eq!(vec![(1, 0), (0, 1)], low_points(text, LowPointOptions::default()));
+        assert_eq!(vec![(0, 1)], low_points(text, EIGHT));
+    }
+
+    #[test]
+    fn flat_bottoms() {
+        let text = "9999\n9229\n9939";
+        assert_eq!(Vec::<Pos>::new(), low_points(text, LowPointOptions::default()));
+        let regions = low_regions(&heightmap(text), PLATEAUS);
+        assert_eq!(vec![LowRegion { height: 2, cells: vec![(1, 1), (2, 1)] }], regions);
+        assert_eq!(3, regions[0].risk());
+    }
+
+    #[test]
+    fn plateaus_with_a_way_down_are_not_low() {
+        assert_eq!(vec![(3, 0)], low_points("2221", PLATEAUS));
+        assert_eq!(vec![(0, 0)], low_points("55", PLATEAUS));
+    }
+
+    #[test]
+    fn big_bumpy_maps() {
+        // Nothing flat to speak of, so every cell starts its own plateau
+        let text: Vec<String> = (0..1000)
+            .map(|y| (0..1000).map(|x| char::from(b'0' + ((x * 7 + y * 3) % 10) as u8)).collect())
+            .collect();
+        let heightmap = heightmap(&text.join("\n"));
+        assert_eq!(
+            low_regions(&heightmap, LowPointOptions::default()).len(),
+            low_regions(&heightmap, PLATEAUS).len()
+        );
+    }
+
+    #[test]
+    fn flat_basins() {
+        let basins = Basins::map(&heightmap("9999\n9229\n9939"), PLATEAUS);
+        assert_eq!(vec![(1, 1)], basins.low_points);
+        assert_eq!(vec![3], basins.sizes);
     }
 }
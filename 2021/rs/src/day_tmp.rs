@@ -1,34 +1,29 @@
 // Day n
 
 use crate::Answer;
+use crate::error::{Error, Result};
+use crate::input::Input;
+use crate::solver::Solver;
 
-pub fn my_puzzle_name(input: Vec<String>) -> Answer {
-    Answer::U32(0, 0)
-}
+pub struct MyPuzzleName;
+
+impl Solver for MyPuzzleName {
+    type Parsed = Vec<String>;
 
-#[cfg(test)]
-mod answer_tests {
-    use super::*;
-    use crate::read_input;
+    fn parse(input: &Input) -> Result<Self::Parsed> {
+        Ok(input.lines().map(|line| line.text.to_string()).collect())
+    }
 
-    #[test]
-    fn first_puzzle() {
-        let input = read_input("../testinputs/08.txt");
-        if let Answer::U32(first, _) = my_puzzle_name(input) {
-            assert_eq!(26, first);
-        } else {
-            panic!("wrong type");
-        }
+    fn part1(input: &Self::Parsed) -> Result<Answer> {
+        Ok(Answer::U32(0))
     }
 
-    #[test]
-    #[ignore]
-    fn second_puzzle() {
-        let input = read_input("../testinputs/08.txt");
-        if let Answer::U32(_, second) = my_puzzle_name(input) {
-            assert_eq!(61229, second);
-        } else {
-            panic!("wrong type");
-        }
+    fn part2(input: &Self::Parsed) -> Result<Answer> {
+        Ok(Answer::U32(0))
     }
 }
+
+example_tests! {
+    MyPuzzleName;
+    example: "NN.txt" => _, _;
+}
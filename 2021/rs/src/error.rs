@@ -0,0 +1,116 @@
+// The one error type every solver reports bad input with.
+//
+// Parsers usually know the line and column they choked on, but not which file
+// they were reading; the runner fills that in before printing, so the message
+// comes out like a compiler diagnostic: `../inputs/04.txt:3:7: expected ...`.
+
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub file: Option<String>,
+    // Both 1-based, and None when the problem isn't tied to one spot.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub expected: String,
+    pub found: Option<String>,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    // Something about the input as a whole was wrong.
+    pub fn input(expected: impl Into<String>) -> Self {
+        Error {
+            file: None,
+            line: None,
+            column: None,
+            expected: expected.into(),
+            found: None,
+        }
+    }
+
+    pub fn at(line: usize, column: usize, expected: impl Into<String>) -> Self {
+        Error::input(expected).on_line(line).at_column(column)
+    }
+
+    pub fn on_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn at_column(mut self, column: usize) -> Self {
+        self.column = Some(column);
+        self
+    }
+
+    pub fn found(mut self, found: impl Into<String>) -> Self {
+        self.found = Some(found.into());
+        self
+    }
+
+    pub fn in_file(mut self, file: &str) -> Self {
+        self.file = Some(file.to_string());
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = [
+            self.file.clone(),
+            self.line.map(|n| n.to_string()),
+            self.column.map(|n| n.to_string()),
+        ];
+        let location: Vec<String> = location.into_iter().flatten().collect();
+        if !location.is_empty() {
+            write!(f, "{}: ", location.join(":"))?;
+        }
+
+        write!(f, "expected {}", self.expected)?;
+        if let Some(found) = &self.found {
+            write!(f, ", found {:?}", found)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+// 1-based column of `token` within `line`, when `token` was sliced out of it.
+pub fn column_of(line: &str, token: &str) -> Option<usize> {
+    let start = line.as_ptr() as usize;
+    let offset = (token.as_ptr() as usize).checked_sub(start)?;
+    if offset + token.len() <= line.len() {
+        Some(line[..offset].chars().count() + 1)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn full_diagnostic() {
+        let error = Error::at(3, 7, "a number").found("x").in_file("../inputs/04.txt");
+        assert_eq!("../inputs/04.txt:3:7: expected a number, found \"x\"", error.to_string());
+    }
+
+    #[test]
+    fn whole_input_diagnostic() {
+        let error = Error::input("at least one line");
+        assert_eq!("expected at least one line", error.to_string());
+    }
+
+    #[test]
+    fn token_columns() {
+        let line = "12, 34,x";
+        let tokens: Vec<&str> = line.split(',').collect();
+        assert_eq!(Some(1), column_of(line, tokens[0]));
+        assert_eq!(Some(4), column_of(line, tokens[1]));
+        assert_eq!(Some(8), column_of(line, tokens[2]));
+        assert_eq!(None, column_of(line, "elsewhere"));
+    }
+}
@@ -0,0 +1,271 @@
+// A rectangular grid of cells, stored flat in row-major order.
+//
+// Positions are `(x, y)` with x running along a row and y down the columns,
+// both starting at 0 in the top left, which is how the puzzles draw them.
+
+use crate::error::Result;
+use crate::input::Input;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+pub type Pos = (usize, usize);
+
+// Which cells count as touching: just the four sharing an edge, or the
+// diagonals as well.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Connectivity {
+    #[default]
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Four => &[(0, -1), (0, 1), (-1, 0), (1, 0)],
+            Connectivity::Eight => &[
+                (-1, -1), (0, -1), (1, -1),
+                (-1, 0), (1, 0),
+                (-1, 1), (0, 1), (1, 1),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Grid {
+            cells: vec![fill; width * height],
+            width,
+            height,
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    // Wrap row-major `cells`, which must fill a whole number of rows.
+    pub fn from_vec(width: usize, cells: Vec<T>) -> Self {
+        assert!(width > 0 && cells.len().is_multiple_of(width), "cells don't fill rows of {}", width);
+        Grid {
+            height: cells.len() / width,
+            cells,
+            width,
+        }
+    }
+
+    // None if the rows aren't all the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Self> {
+        let width = rows.first().map(Vec::len)?;
+        if width == 0 || rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+        Some(Grid::from_vec(width, rows.into_iter().flatten().collect()))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn contains(&self, (x, y): Pos) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos).then(|| &self.cells[self.offset(pos)])
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if !self.contains(pos) {
+            return None;
+        }
+        let offset = self.offset(pos);
+        Some(&mut self.cells[offset])
+    }
+
+    // Every position, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column {} is outside the {}x{} grid", x, self.width, self.height);
+        self.cells[x..].iter().step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |x| self.column(x))
+    }
+
+    // Up, down, left and right of `pos`, leaving out any that are off the grid.
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbours(pos, Connectivity::Four)
+    }
+
+    // The 4-neighbours plus the diagonals.
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbours(pos, Connectivity::Eight)
+    }
+
+    pub fn neighbours(&self, (x, y): Pos, connectivity: Connectivity) -> impl Iterator<Item = Pos> + '_ {
+        connectivity.offsets().iter()
+            .filter_map(move |(dx, dy)| Some((x.checked_add_signed(*dx)?, y.checked_add_signed(*dy)?)))
+            .filter(|pos| self.contains(*pos))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            cells: self.cells.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    // One line per row, one character per cell.
+    pub fn render(&self, mut f: impl FnMut(&T) -> char) -> String {
+        self.rows()
+            .map(|row| row.iter().map(&mut f).chain(std::iter::once('\n')).collect::<String>())
+            .collect()
+    }
+
+    fn offset(&self, (x, y): Pos) -> usize {
+        y * self.width + x
+    }
+}
+
+impl Grid<u8> {
+    // A grid of single digits, one row per line.
+    pub fn from_digits(input: &Input) -> Result<Self> {
+        // digit_rows has already checked the rows line up
+        Ok(Grid::from_rows(input.digit_rows()?).unwrap())
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos).unwrap_or_else(|| panic!("{:?} is outside the grid", pos))
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside the {}x{} grid", pos, width, height))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            f.write_str("\n")?;
+            f.write_fmt(format_args!("{:?}", row))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod grid_tests {
+    use super::*;
+
+    fn sample() -> Grid<u8> {
+        Grid::from_digits(&Input::from("123\n456")).unwrap()
+    }
+
+    #[test]
+    fn indexing() {
+        let grid = sample();
+        assert_eq!((3, 2), (grid.width(), grid.height()));
+        assert_eq!(6, grid[(2, 1)]);
+        assert_eq!(Some(&4), grid.get((0, 1)));
+        assert_eq!(None, grid.get((3, 0)));
+        assert_eq!(None, grid.get((0, 2)));
+    }
+
+    #[test]
+    fn mutation() {
+        let mut grid = Grid::new(2, 2, 0u32);
+        grid[(1, 0)] += 3;
+        *grid.get_mut((0, 1)).unwrap() += 1;
+        assert_eq!(&[0, 3, 1, 0], grid.as_slice());
+        assert_eq!(None, grid.get_mut((2, 0)));
+        assert_eq!(None, grid.get_mut((0, usize::MAX)));
+        assert_eq!(None, grid.get((usize::MAX, usize::MAX)));
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let grid = sample();
+        assert_eq!(vec![&[1, 2, 3][..], &[4, 5, 6][..]], grid.rows().collect::<Vec<_>>());
+        assert_eq!(&[4, 5, 6], grid.row(1));
+        assert_eq!(vec![2, 5], grid.column(1).copied().collect::<Vec<_>>());
+        let columns: Vec<Vec<u8>> = grid.columns().map(|c| c.copied().collect()).collect();
+        assert_eq!(vec![vec![1, 4], vec![2, 5], vec![3, 6]], columns);
+    }
+
+    #[test]
+    #[should_panic(expected = "column 3 is outside the 3x2 grid")]
+    fn columns_stop_at_the_edge() {
+        sample().column(3);
+    }
+
+    #[test]
+    fn neighbours_stay_on_the_grid() {
+        let grid = sample();
+        let mut corner: Vec<Pos> = grid.neighbours4((0, 0)).collect();
+        corner.sort();
+        assert_eq!(vec![(0, 1), (1, 0)], corner);
+        assert_eq!(3, grid.neighbours4((1, 1)).count());
+        assert_eq!(3, grid.neighbours8((0, 0)).count());
+        assert_eq!(5, grid.neighbours8((1, 0)).count());
+    }
+
+    #[test]
+    fn iterates_row_by_row() {
+        let grid = sample();
+        let cells: Vec<(Pos, u8)> = grid.iter().map(|(pos, v)| (pos, *v)).take(4).collect();
+        assert_eq!(vec![((0, 0), 1), ((1, 0), 2), ((2, 0), 3), ((0, 1), 4)], cells);
+    }
+
+    #[test]
+    fn text_rendering() {
+        let grid = sample();
+        assert_eq!("123\n456\n", grid.render(|d| char::from(b'0' + d)));
+        assert_eq!("..#\n###\n", grid.render(|d| if *d > 2 { '#' } else { '.' }));
+    }
+
+    #[test]
+    fn rejects_ragged_rows() {
+        assert_eq!(None, Grid::from_rows(vec![vec![1, 2], vec![3]]));
+        assert_eq!(None, Grid::<u8>::from_rows(vec![]));
+    }
+}
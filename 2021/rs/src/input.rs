@@ -0,0 +1,202 @@
+// A puzzle input, read once and handed out in borrowed pieces.
+//
+// Most puzzles come in a handful of shapes: one value per line, a line of
+// comma-separated numbers, blocks separated by blank lines, a grid of digits,
+// or two halves split by something like ` | `. The helpers here cover those,
+// and every piece remembers which line it came from so parse errors can point
+// straight at the problem.
+
+use crate::error::{column_of, Error, Result};
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Input {
+    text: String,
+}
+
+impl Input {
+    pub fn new(text: String) -> Self {
+        Input { text }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = Line<'_>> {
+        self.text.lines().enumerate()
+            .map(|(i, text)| Line { number: i + 1, text })
+    }
+
+    pub fn first_line(&self, expected: &str) -> Result<Line<'_>> {
+        self.lines().next().ok_or_else(|| Error::input(expected))
+    }
+
+    // Runs of non-blank lines; any number of blank lines separates them.
+    pub fn blocks(&self) -> impl Iterator<Item = Vec<Line<'_>>> {
+        let mut lines = self.lines().peekable();
+        std::iter::from_fn(move || {
+            while lines.next_if(|line| line.is_blank()).is_some() {}
+            let mut block = Vec::new();
+            while let Some(line) = lines.next_if(|line| !line.is_blank()) {
+                block.push(line);
+            }
+            (!block.is_empty()).then_some(block)
+        })
+    }
+
+    // Every line as a row of single digits, checking the rows form a rectangle.
+    pub fn digit_rows(&self) -> Result<Vec<Vec<u8>>> {
+        let rows: Vec<Vec<u8>> = self.lines()
+            .map(|line| line.digits())
+            .collect::<Result<_>>()?;
+
+        let width = rows.first().map(Vec::len).unwrap_or(0);
+        if width == 0 {
+            return Err(Error::input("at least one row of digits"));
+        }
+        if let Some(line) = self.lines().find(|line| line.text.chars().count() != width) {
+            return Err(line.error(format!("a row of {} digits", width)));
+        }
+
+        Ok(rows)
+    }
+}
+
+impl From<&str> for Input {
+    fn from(text: &str) -> Self {
+        Input::new(text.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Line<'a> {
+    // 1-based, to match what an editor shows.
+    pub number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    pub fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    // An error about the whole line.
+    pub fn error(&self, expected: impl Into<String>) -> Error {
+        Error::input(expected).on_line(self.number).found(self.text)
+    }
+
+    // An error pointing at `token`, which should be a slice of this line.
+    pub fn error_at(&self, token: &str, expected: impl Into<String>) -> Error {
+        let error = Error::input(expected).on_line(self.number).found(token);
+        match column_of(self.text, token) {
+            Some(column) => error.at_column(column),
+            None => error,
+        }
+    }
+
+    pub fn parse<T: FromStr>(&self, token: &str, expected: &str) -> Result<T> {
+        token.parse().map_err(|_| self.error_at(token, expected))
+    }
+
+    // The whole line as one value.
+    pub fn value<T: FromStr>(&self, expected: &str) -> Result<T> {
+        self.parse(self.text, expected)
+    }
+
+    pub fn csv<T: FromStr>(&self, expected: &str) -> Result<Vec<T>> {
+        self.text.split(',')
+            .map(|token| self.parse(token, expected))
+            .collect()
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = &'a str> {
+        self.text.split_whitespace()
+    }
+
+    // Whitespace-separated values, however they're aligned.
+    pub fn values<T: FromStr>(&self, expected: &str) -> Result<Vec<T>> {
+        self.text.split_whitespace()
+            .map(|token| self.parse(token, expected))
+            .collect()
+    }
+
+    pub fn digits(&self) -> Result<Vec<u8>> {
+        self.text.char_indices()
+            .map(|(i, c)| {
+                c.to_digit(10)
+                    .map(|d| d as u8)
+                    .ok_or_else(|| self.error_at(&self.text[i..i + c.len_utf8()], "a digit"))
+            })
+            .collect()
+    }
+
+    // The two halves either side of `separator`, like the `a | b` of day 8.
+    pub fn split_once(&self, separator: &str, expected: &str) -> Result<(&'a str, &'a str)> {
+        self.text.split_once(separator)
+            .ok_or_else(|| self.error(expected))
+    }
+}
+
+#[cfg(test)]
+mod input_tests {
+    use super::*;
+
+    #[test]
+    fn numbered_lines() {
+        let input = Input::from("a\nb\n\nc");
+        let numbers: Vec<(usize, &str)> = input.lines().map(|l| (l.number, l.text)).collect();
+        assert_eq!(vec![(1, "a"), (2, "b"), (3, ""), (4, "c")], numbers);
+    }
+
+    #[test]
+    fn blocks_skip_any_blank_lines() {
+        let input = Input::from("\n1 2\n3 4\n\n\n5\n");
+        let blocks: Vec<Vec<usize>> = input.blocks()
+            .map(|block| block.iter().map(|l| l.number).collect())
+            .collect();
+        assert_eq!(vec![vec![2, 3], vec![6]], blocks);
+    }
+
+    #[test]
+    fn comma_separated() {
+        let input = Input::from("3,4,3,1,2");
+        assert_eq!(Ok(vec![3u8, 4, 3, 1, 2]), input.first_line("ages").unwrap().csv("an age"));
+    }
+
+    #[test]
+    fn comma_separated_error_column() {
+        let input = Input::from("16,1,x2");
+        let error = input.first_line("positions").unwrap().csv::<i64>("a position").unwrap_err();
+        assert_eq!((Some(1), Some(6)), (error.line, error.column));
+    }
+
+    #[test]
+    fn aligned_values() {
+        let input = Input::from(" 8  2 23");
+        assert_eq!(Ok(vec![8u32, 2, 23]), input.first_line("numbers").unwrap().values("a number"));
+    }
+
+    #[test]
+    fn digit_grid() {
+        let input = Input::from("219\n398");
+        assert_eq!(Ok(vec![vec![2, 1, 9], vec![3, 9, 8]]), input.digit_rows());
+    }
+
+    #[test]
+    fn digit_grid_errors() {
+        let error = Input::from("219\n3x8").digit_rows().unwrap_err();
+        assert_eq!((Some(2), Some(2)), (error.line, error.column));
+        let error = Input::from("219\n39").digit_rows().unwrap_err();
+        assert_eq!(Some(2), error.line);
+        assert!(Input::from("").digit_rows().is_err());
+    }
+
+    #[test]
+    fn split_halves() {
+        let input = Input::from("ab cd | ef");
+        let line = input.first_line("a line").unwrap();
+        assert_eq!(Ok(("ab cd", "ef")), line.split_once(" | ", "two halves"));
+        assert!(line.split_once(" + ", "two halves").is_err());
+    }
+}
@@ -1,6 +1,22 @@
 #![allow(unused)]
 use std::env;
 use std::fs;
+use std::process;
+
+#[macro_use]
+mod testing;
+
+mod answer;
+mod bench;
+mod bignum;
+mod cli;
+mod error;
+mod grid;
+mod input;
+mod netpbm;
+mod scaffold;
+mod solver;
+mod verify;
 
 mod day_01;
 mod day_02;
@@ -12,55 +28,222 @@ mod day_07;
 mod day_08;
 mod day_09;
 
+pub use answer::Answer;
+use solver::{Day, Solver};
+
+const DAYS: &[Day] = &[
+    Day::new::<day_01::DepthMeasurements>(1),
+    Day::new::<day_02::SubPosition>(2),
+    Day::new::<day_03::BinaryDiagnostic>(3),
+    Day::new::<day_04::PlayBingo>(4),
+    Day::new::<day_05::HydrothermalVents>(5),
+    Day::new::<day_06::Lanternfish>(6),
+    Day::new::<day_07::CrabTargetAlignment>(7),
+    Day::new::<day_08::SevenSegDecode>(8),
+    Day::new::<day_09::TubeSmoke>(9),
+];
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
-
-    calculate_answer("../inputs/01.txt", day_01::depth_measurements);
-    run_exercise("../inputs/02.txt", day_02::sub_position);
-    run_exercise("../inputs/03.txt", day_03::binary_diagnostic);
-    run_exercise("../inputs/04.txt", day_04::play_bingo);
-    run_exercise("../inputs/05.txt", day_05::hydrothermal_vents);
-    calculate_answer("../inputs/06.txt", day_06::lanternfish);
-    calculate_answer("../inputs/07.txt", day_07::crab_target_alignment);
-    calculate_answer("../inputs/08.txt", day_08::seven_seg_decode);
-    calculate_answer("../inputs/09.txt", day_09::tube_smoke);
-}
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let command = match cli::parse(args) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, cli::USAGE);
+            process::exit(2);
+        },
+    };
 
-pub enum Answer {
-    U32(u32, u32),
-    U64(u64, u64),
-    I64(i64, i64),
+    match command {
+        cli::Command::Run(opts) => for_each_day(&opts, |entry| run_day(entry, &opts)),
+        cli::Command::Bench(opts, settings) => {
+            for_each_day(&opts, |entry| bench_day(entry, &opts, &settings))
+        },
+        cli::Command::Verify(opts, record) => {
+            if !verify_days(&opts, record) {
+                process::exit(1);
+            }
+        },
+        cli::Command::Render(opts, out_dir) => {
+            for_each_day(&opts, |entry| render_day(entry, &opts, &out_dir))
+        },
+        cli::Command::NewDay(day, name) => {
+            match scaffold::new_day(day, name.as_deref()) {
+                Ok(report) => report.iter().for_each(|line| println!("{}", line)),
+                Err(message) => {
+                    eprintln!("error: {}", message);
+                    process::exit(1);
+                },
+            }
+        },
+        cli::Command::Help => println!("{}", cli::USAGE),
+    }
 }
 
-fn run_exercise(input_file: &str, calculator: fn(Vec<String>) -> (u32, u32)) {
-    let mut input = read_input(input_file);
+// Either the day picked on the command line, or all of them.
+fn for_each_day(opts: &cli::RunOptions, mut f: impl FnMut(&Day)) {
+    let Some(day) = opts.day else {
+        DAYS.iter().for_each(f);
+        return;
+    };
+
+    match DAYS.iter().find(|entry| entry.number == day) {
+        Some(entry) => f(entry),
+        None => eprintln!("Day {} hasn't been solved yet.", day),
+    }
+}
 
-    let (first_answer, second_answer) = calculator(input);
+fn run_day(entry: &Day, opts: &cli::RunOptions) {
+    let day = entry.number;
+    let input_file = &opts.input_path(day);
+    let answers = load_input(input_file)
+        .and_then(|input| (entry.run)(&input, opts.part));
 
     println!("Results from {}:", input_file);
-    println!("First answer is {}, second answer is {}.",
-        first_answer, second_answer);
+    let answers = match answers {
+        Ok(answers) => answers,
+        Err(error) => {
+            eprintln!("error: {}", error.in_file(input_file));
+            return;
+        },
+    };
+
+    for (part, answer) in answers {
+        match (part, answer) {
+            (Part::One, Ok(answer)) => println!("First answer is {}.", answer),
+            (Part::Two, Ok(answer)) => println!("Second answer is {}.", answer),
+            (_, Err(error)) => eprintln!("error: {}", error.in_file(input_file)),
+        }
+    }
 }
 
-fn calculate_answer(input_file: &str, calculator: fn(Vec<String>) -> Answer) {
-    let mut input = read_input(input_file);
+fn bench_day(entry: &Day, opts: &cli::RunOptions, settings: &bench::BenchSettings) {
+    let input_file = &opts.input_path(entry.number);
+    let timings = load_input(input_file)
+        .and_then(|input| (entry.bench)(&input, opts.part, settings));
 
-    let answers = calculator(input);
+    println!("Timings for {} ({} samples after {} warm-up runs):",
+        input_file, settings.samples, settings.warmup);
+    match timings {
+        Ok(timings) => println!("{}\n", bench::report(&timings)),
+        Err(error) => eprintln!("error: {}", error.in_file(input_file)),
+    }
+}
 
-    let (first_answer, second_answer) = match answers {
-        Answer::U32(first, second) => (first.into(), second.into()),
-        Answer::I64(first, second) => (first.try_into().unwrap(), second.try_into().unwrap()),
-        Answer::U64(first, second) => (first, second),
+// Write each of the day's renderings to `out_dir` as day_NN_<name>.pgm/ppm/csv/txt.
+fn render_day(entry: &Day, opts: &cli::RunOptions, out_dir: &str) {
+    let input_file = &opts.input_path(entry.number);
+    let renders = match load_input(input_file).and_then(|input| (entry.render)(&input)) {
+        Ok(renders) => renders,
+        Err(error) => {
+            eprintln!("error: {}", error.in_file(input_file));
+            return;
+        },
     };
 
-    println!("Results from {}:", input_file);
-    println!("First answer is {}, second answer is {}.",
-        first_answer, second_answer);
+    if renders.is_empty() {
+        println!("Day {:02} has nothing to render.", entry.number);
+        return;
+    }
+    if let Err(e) = fs::create_dir_all(out_dir) {
+        eprintln!("error: couldn't create {}: {}", out_dir, e);
+        return;
+    }
+    for (name, rendering) in renders {
+        let path = format!("{}/day_{:02}_{}.{}", out_dir, entry.number, name, rendering.extension());
+        match fs::write(&path, rendering.contents()) {
+            Ok(()) => println!("Wrote {} from {}", path, input_file),
+            Err(e) => eprintln!("error: couldn't write {}: {}", path, e),
+        }
+    }
+}
+
+// Check every answer against the manifest, returning false if anything
+// failed or couldn't be answered at all.
+fn verify_days(opts: &cli::RunOptions, record: bool) -> bool {
+    let mut manifest = match verify::Manifest::load(verify::MANIFEST) {
+        Ok(manifest) => manifest,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return false;
+        },
+    };
+
+    let mut all_good = true;
+    let mut recorded = 0;
+    for_each_day(opts, |entry| {
+        let input_file = &opts.input_path(entry.number);
+        let run = load_input(input_file).and_then(|input| {
+            Ok((verify::checksum(input.text().as_bytes()), (entry.run)(&input, opts.part)?))
+        });
+        let (checksum, answers) = match run {
+            Ok(run) => run,
+            Err(error) => {
+                println!("Day {:02}: error: {}", entry.number, error.in_file(input_file));
+                all_good = false;
+                return;
+            },
+        };
+
+        for (part, answer) in answers {
+            let label = format!("Day {:02} {}", entry.number, part.label());
+            let answer = match answer {
+                Ok(answer) => answer,
+                Err(error) => {
+                    println!("{}: error: {}", label, error.in_file(input_file));
+                    all_good = false;
+                    continue;
+                },
+            };
+
+            let key = verify::Key::new(entry.number, part, checksum);
+            match manifest.check(&key, &answer) {
+                verify::Verdict::Pass => println!("{}: pass ({})", label, answer),
+                verify::Verdict::Fail { expected } => {
+                    println!("{}: FAIL (got {}, expected {})", label, answer, expected);
+                    all_good = false;
+                },
+                verify::Verdict::Unknown if record => {
+                    println!("{}: recorded ({})", label, answer);
+                    manifest.record(key, &answer);
+                    recorded += 1;
+                },
+                verify::Verdict::Unknown => println!("{}: unknown ({})", label, answer),
+            }
+        }
+    });
+
+    if recorded > 0 {
+        if let Err(error) = manifest.save(verify::MANIFEST) {
+            eprintln!("error: {}", error);
+            return false;
+        }
+    }
+    all_good
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub fn label(self) -> &'static str {
+        match self {
+            Part::One => "part 1",
+            Part::Two => "part 2",
+        }
+    }
+}
+
+fn load_input(input_file: &str) -> error::Result<input::Input> {
+    fs::read_to_string(input_file)
+        .map(input::Input::new)
+        .map_err(|e| error::Error::input("a readable input file").found(e.to_string()))
 }
 
-fn read_input(input_file: &str) -> Vec<String> {
-    let input = fs::read_to_string(input_file)
-        .expect("Something went wrong reading the input file")
-        .lines().map(String::from).collect();
-    input
+#[cfg(test)]
+fn read_input(input_file: &str) -> input::Input {
+    load_input(input_file).expect("Something went wrong reading the input file")
 }
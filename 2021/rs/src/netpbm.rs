@@ -0,0 +1,125 @@
+// Images in the plain (ASCII) netpbm formats, for eyeballing what a solver
+// sees without needing anything but an image viewer.
+//
+// Plain PGM (P2) is for grayscale and plain PPM (P3) for colour. They're
+// bulky, but they're text, so they diff nicely and need nothing beyond std
+// to write.
+
+use crate::grid::Grid;
+
+pub type Rgb = [u8; 3];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Image {
+    // Each pixel runs from 0 (black) to `max` (white).
+    Gray { pixels: Grid<u8>, max: u8 },
+    Color(Grid<Rgb>),
+}
+
+impl Image {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Image::Gray { .. } => "pgm",
+            Image::Color(_) => "ppm",
+        }
+    }
+
+    pub fn to_plain(&self) -> String {
+        match self {
+            Image::Gray { pixels, max } => {
+                let samples = pixels.rows().map(|row| row.to_vec());
+                plain("P2", pixels, *max, samples)
+            },
+            Image::Color(pixels) => {
+                let samples = pixels.rows().map(|row| row.iter().flatten().copied().collect());
+                plain("P3", pixels, 255, samples)
+            },
+        }
+    }
+}
+
+// The header, then each row of samples. The format asks for lines of no more
+// than 70 characters, so long rows get wrapped.
+fn plain<T>(magic: &str, pixels: &Grid<T>, max: u8, rows: impl Iterator<Item = Vec<u8>>) -> String {
+    let mut out = format!("{}\n{} {}\n{}\n", magic, pixels.width(), pixels.height(), max);
+    for row in rows {
+        let mut line = String::new();
+        for sample in row {
+            let sample = sample.to_string();
+            if !line.is_empty() && line.len() + 1 + sample.len() > 70 {
+                out.push_str(&line);
+                out.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&sample);
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+// A colour for the `n`th of some set of things, stepping round the hue wheel
+// by the golden angle so neighbouring numbers come out looking different.
+pub fn palette(n: usize) -> Rgb {
+    let hue = (n as f64 * 137.507_764) % 360.0;
+    hsv(hue, 0.65, 0.95)
+}
+
+// `colour` faded towards black, keeping `fraction` of its brightness.
+pub fn shade(colour: Rgb, fraction: f64) -> Rgb {
+    colour.map(|c| (f64::from(c) * fraction.clamp(0.0, 1.0)).round() as u8)
+}
+
+fn hsv(hue: f64, saturation: f64, value: f64) -> Rgb {
+    let chroma = value * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    [r, g, b].map(|c| ((c + m) * 255.0).round() as u8)
+}
+
+#[cfg(test)]
+mod netpbm_tests {
+    use super::*;
+
+    #[test]
+    fn plain_gray() {
+        let image = Image::Gray { pixels: Grid::from_vec(3, vec![0, 4, 9, 9, 1, 0]), max: 9 };
+        assert_eq!("P2\n3 2\n9\n0 4 9\n9 1 0\n", image.to_plain());
+        assert_eq!("pgm", image.extension());
+    }
+
+    #[test]
+    fn plain_color() {
+        let image = Image::Color(Grid::from_vec(2, vec![[255, 0, 0], [0, 0, 255]]));
+        assert_eq!("P3\n2 1\n255\n255 0 0 0 0 255\n", image.to_plain());
+        assert_eq!("ppm", image.extension());
+    }
+
+    #[test]
+    fn long_rows_are_wrapped() {
+        let image = Image::Gray { pixels: Grid::new(40, 1, 200), max: 255 };
+        let plain = image.to_plain();
+        assert!(plain.lines().all(|line| line.len() <= 70));
+        assert_eq!(40, plain.lines().skip(3).flat_map(str::split_whitespace).count());
+    }
+
+    #[test]
+    fn colours() {
+        assert_eq!([242, 85, 85], palette(0));
+        assert_ne!(palette(1), palette(2));
+        assert_eq!([121, 43, 43], shade(palette(0), 0.5));
+    }
+}
@@ -0,0 +1,181 @@
+// Scaffolding for a new day, built from the `day_tmp.rs` template.
+//
+// Everything here refuses to overwrite: an existing day module, input file or
+// registration is left alone, so running it twice is harmless.
+
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+
+const TEMPLATE: &str = include_str!("day_tmp.rs");
+const MAIN: &str = "src/main.rs";
+
+// Create and register day `day`, returning a line describing each step.
+pub fn new_day(day: u8, name: Option<&str>) -> Result<Vec<String>, String> {
+    let name = match name {
+        Some(name) => solver_name(name)?,
+        None => format!("Day{:02}", day),
+    };
+    let mut report = Vec::new();
+
+    let module = format!("src/day_{:02}.rs", day);
+    report.push(create_file(&module, &render_template(day, &name))?);
+
+    let main = fs::read_to_string(MAIN).map_err(|e| format!("couldn't read {}: {}", MAIN, e))?;
+    match register(&main, day, &name)? {
+        Some(registered) => {
+            fs::write(MAIN, registered).map_err(|e| format!("couldn't write {}: {}", MAIN, e))?;
+            report.push(format!("registered day {} in {}", day, MAIN));
+        },
+        None => report.push(format!("day {} was already registered in {}", day, MAIN)),
+    }
+
+    for dir in ["../inputs", "../testinputs"] {
+        report.push(create_file(&format!("{}/{:02}.txt", dir, day), "")?);
+    }
+
+    Ok(report)
+}
+
+fn create_file(path: &str, contents: &str) -> Result<String, String> {
+    use std::io::Write;
+
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(mut file) => {
+            file.write_all(contents.as_bytes())
+                .map_err(|e| format!("couldn't write {}: {}", path, e))?;
+            Ok(format!("created {}", path))
+        },
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(format!("kept existing {}", path)),
+        Err(e) => Err(format!("couldn't create {}: {}", path, e)),
+    }
+}
+
+// Turn `smoke_basin` (or `SmokeBasin`) into the solver's type name.
+fn solver_name(name: &str) -> Result<String, String> {
+    let valid = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name.starts_with(|c: char| c.is_ascii_alphabetic());
+    if !valid {
+        return Err(format!("'{}' isn't usable as a puzzle name", name));
+    }
+
+    Ok(name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect())
+}
+
+fn render_template(day: u8, name: &str) -> String {
+    TEMPLATE
+        .replacen("// Day n", &format!("// Day {}", day), 1)
+        .replace("MyPuzzleName", name)
+        .replace("\"NN.txt\"", &format!("\"{:02}.txt\"", day))
+}
+
+// Add the `mod` line and the day table entry to main.rs, keeping both in day
+// order. Returns None if the day is already there.
+fn register(main: &str, day: u8, name: &str) -> Result<Option<String>, String> {
+    let module = format!("day_{:02}", day);
+    if main.contains(&format!("mod {};", module)) {
+        return Ok(None);
+    }
+
+    let mut lines: Vec<String> = main.lines().map(String::from).collect();
+
+    let mod_line = format!("mod {};", module);
+    let mod_at = insertion_point(&lines, "mod day_", day)
+        .ok_or("couldn't find the day modules in main.rs")?;
+    lines.insert(mod_at, mod_line);
+
+    let entry = format!("    Day::new::<{}::{}>({}),", module, name, day);
+    let entry_at = insertion_point(&lines, "    Day::new::<day_", day)
+        .ok_or("couldn't find the DAYS table in main.rs")?;
+    lines.insert(entry_at, entry);
+
+    Ok(Some(lines.join("\n") + "\n"))
+}
+
+// Where a line for `day` goes among the lines starting with `prefix`,
+// which are followed by a two digit day number.
+fn insertion_point(lines: &[String], prefix: &str, day: u8) -> Option<usize> {
+    let existing: Vec<(usize, u8)> = lines.iter().enumerate()
+        .filter_map(|(i, line)| {
+            let number = line.strip_prefix(prefix)?.get(..2)?.parse().ok()?;
+            Some((i, number))
+        })
+        .collect();
+
+    let (first, _) = existing.first()?;
+    Some(existing.iter()
+        .filter(|(_, number)| *number < day)
+        .map(|(i, _)| i + 1)
+        .next_back()
+        .unwrap_or(*first))
+}
+
+#[cfg(test)]
+mod scaffold_tests {
+    use super::*;
+
+    const MAIN_RS: &str = "\
+mod cli;
+
+mod day_01;
+mod day_03;
+
+const DAYS: &[Day] = &[
+    Day::new::<day_01::DepthMeasurements>(1),
+    Day::new::<day_03::BinaryDiagnostic>(3),
+];
+";
+
+    #[test]
+    fn names() {
+        assert_eq!(Ok("SmokeBasin".to_string()), solver_name("smoke_basin"));
+        assert_eq!(Ok("SmokeBasin".to_string()), solver_name("SmokeBasin"));
+        assert!(solver_name("9lives").is_err());
+        assert!(solver_name("smoke-basin").is_err());
+    }
+
+    #[test]
+    fn template_is_filled_in() {
+        let rendered = render_template(10, "SyntaxScoring");
+        assert!(rendered.starts_with("// Day 10\n"));
+        assert!(rendered.contains("pub struct SyntaxScoring;"));
+        assert!(rendered.contains("example: \"10.txt\" => _, _;"));
+        assert!(!rendered.contains("MyPuzzleName"));
+    }
+
+    #[test]
+    fn registers_in_order() {
+        let registered = register(MAIN_RS, 2, "SubPosition").unwrap().unwrap();
+        assert_eq!("\
+mod cli;
+
+mod day_01;
+mod day_02;
+mod day_03;
+
+const DAYS: &[Day] = &[
+    Day::new::<day_01::DepthMeasurements>(1),
+    Day::new::<day_02::SubPosition>(2),
+    Day::new::<day_03::BinaryDiagnostic>(3),
+];
+", registered);
+    }
+
+    #[test]
+    fn registers_at_the_end() {
+        let registered = register(MAIN_RS, 10, "Day10").unwrap().unwrap();
+        assert!(registered.contains("mod day_03;\nmod day_10;\n"));
+        assert!(registered.contains("(3),\n    Day::new::<day_10::Day10>(10),\n];"));
+    }
+
+    #[test]
+    fn already_registered() {
+        assert_eq!(Ok(None), register(MAIN_RS, 3, "BinaryDiagnostic"));
+    }
+}
@@ -0,0 +1,108 @@
+// The common shape of every day's solution.
+//
+// Each day parses its input once into whatever structure suits it, and then
+// answers the two parts separately from that parsed form. The runner only
+// ever deals with the `Day` table entries, so it doesn't need to know the
+// parsed type of any particular day.
+
+use crate::{bench, Answer, Part};
+use crate::error::Result;
+use crate::input::Input;
+use crate::netpbm::Image;
+
+pub trait Solver {
+    type Parsed;
+
+    fn parse(input: &Input) -> Result<Self::Parsed>;
+    fn part1(parsed: &Self::Parsed) -> Result<Answer>;
+    fn part2(parsed: &Self::Parsed) -> Result<Answer>;
+
+    // Named pictures (or tables) of the parsed input, for days where looking
+    // at it helps.
+    fn render(parsed: &Self::Parsed) -> Renders {
+        Vec::new()
+    }
+}
+
+pub type Answers = Vec<(Part, Result<Answer>)>;
+pub type Renders = Vec<(&'static str, Rendering)>;
+
+// Something `render` writes out: a picture, numbers to plot somewhere else,
+// or a diagram to read as it is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rendering {
+    Image(Image),
+    Csv(String),
+    Text(String),
+}
+
+impl Rendering {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Rendering::Image(image) => image.extension(),
+            Rendering::Csv(_) => "csv",
+            Rendering::Text(_) => "txt",
+        }
+    }
+
+    pub fn contents(&self) -> String {
+        match self {
+            Rendering::Image(image) => image.to_plain(),
+            Rendering::Csv(table) | Rendering::Text(table) => table.clone(),
+        }
+    }
+}
+
+impl From<Image> for Rendering {
+    fn from(image: Image) -> Self {
+        Rendering::Image(image)
+    }
+}
+
+// A registered day: its number plus the solver, erased to a plain function
+// pointer so that days with different `Parsed` types fit in one table.
+pub struct Day {
+    pub number: u8,
+    pub run: fn(&Input, Option<Part>) -> Result<Answers>,
+    pub bench: fn(&Input, Option<Part>, &bench::BenchSettings) -> Result<bench::Timings>,
+    pub render: fn(&Input) -> Result<Renders>,
+}
+
+impl Day {
+    pub const fn new<S: Solver>(number: u8) -> Self {
+        Day {
+            number,
+            run: run::<S>,
+            bench: bench::run::<S>,
+            render: render::<S>,
+        }
+    }
+}
+
+// The part picked on the command line, or both of them.
+pub fn selected_parts(part: Option<Part>) -> Vec<Part> {
+    match part {
+        Some(part) => vec![part],
+        None => vec![Part::One, Part::Two],
+    }
+}
+
+// Parse once and answer the requested part, or both if none was requested.
+// A part that fails doesn't stop the other one from being answered.
+pub fn run<S: Solver>(input: &Input, part: Option<Part>) -> Result<Answers> {
+    let parsed = S::parse(input)?;
+
+    let parts = selected_parts(part);
+
+    let answers = parts.into_iter()
+        .map(|part| match part {
+            Part::One => (part, S::part1(&parsed)),
+            Part::Two => (part, S::part2(&parsed)),
+        })
+        .collect();
+    Ok(answers)
+}
+
+pub fn render<S: Solver>(input: &Input) -> Result<Renders> {
+    Ok(S::render(&S::parse(input)?))
+}
@@ -0,0 +1,99 @@
+// Example tests, declared one line per example input.
+//
+//     example_tests! {
+//         DepthMeasurements;
+//         example: "01.txt" => 7, 5;
+//         larger: "01b.txt" => 12, _;
+//     }
+//
+// Each line names a test module, the file under `../testinputs/`, and the
+// expected answers for part one and part two. An expectation of `_` means we
+// don't know it yet, and the test shows up as ignored instead of passing.
+// Expectations are single tokens compared by their printed form, so write
+// negative numbers in parentheses, like `(-3)`, or as strings.
+
+macro_rules! example_tests {
+    ($solver:ty; $($name:ident: $file:literal => $part1:tt, $part2:tt;)+) => {
+        #[cfg(test)]
+        mod example_tests {
+            use super::*;
+
+            $(
+                mod $name {
+                    use super::*;
+
+                    example_tests!(@part part1, $solver, $file, $part1);
+                    example_tests!(@part part2, $solver, $file, $part2);
+                }
+            )+
+        }
+    };
+
+    (@part $part:ident, $solver:ty, $file:literal, _) => {
+        #[test]
+        #[ignore = "no expected answer yet"]
+        fn $part() {}
+    };
+
+    (@part $part:ident, $solver:ty, $file:literal, $expected:tt) => {
+        #[test]
+        fn $part() {
+            use crate::solver::Solver;
+
+            let input = crate::read_input(concat!("../testinputs/", $file));
+            let parsed = <$solver as Solver>::parse(&input).unwrap();
+            let answer = <$solver as Solver>::$part(&parsed).unwrap();
+            assert_eq!($expected.to_string(), answer.to_string());
+        }
+    };
+}
+
+// A small LCG for randomized tests, so they get the same numbers every run.
+#[cfg(test)]
+pub struct Lcg(u64);
+
+#[cfg(test)]
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Lcg(seed)
+    }
+
+    // The next number below `n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 33) % n
+    }
+}
+
+#[cfg(test)]
+mod macro_tests {
+    use crate::Answer;
+    use crate::error::Result;
+    use crate::input::Input;
+    use crate::solver::Solver;
+
+    // Just counts lines, so it works on any example file.
+    struct LineCount;
+
+    impl Solver for LineCount {
+        type Parsed = usize;
+
+        fn parse(input: &Input) -> Result<Self::Parsed> {
+            Ok(input.lines().count())
+        }
+
+        fn part1(lines: &Self::Parsed) -> Result<Answer> {
+            Ok(Answer::U64(*lines as u64))
+        }
+
+        fn part2(_: &Self::Parsed) -> Result<Answer> {
+            Ok(Answer::from("unused"))
+        }
+    }
+
+    example_tests! {
+        LineCount;
+        depths: "01.txt" => 10, _;
+        fish: "06.txt" => 1, _;
+    }
+}
@@ -0,0 +1,162 @@
+// Known answers for the real puzzle inputs.
+//
+// The example tests only cover `testinputs/`, so a refactor could quietly
+// change what we get for `inputs/NN.txt`. The manifest pins those answers
+// down. Entries are keyed by the checksum of the input as well as the day and
+// part, so swapping in somebody else's input shows up as unknown rather than
+// as a bogus failure.
+
+use crate::{Answer, Part};
+use crate::error::{Error, Result};
+use std::collections::BTreeMap;
+use std::fs;
+
+pub const MANIFEST: &str = "../answers.txt";
+
+// FNV-1a, because it's tiny and stable across Rust versions,
+// which std's DefaultHasher makes no promises about.
+pub fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Key {
+    pub day: u8,
+    pub part: u8,
+    pub checksum: u64,
+}
+
+impl Key {
+    pub fn new(day: u8, part: Part, checksum: u64) -> Self {
+        let part = match part {
+            Part::One => 1,
+            Part::Two => 2,
+        };
+        Key { day, part, checksum }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    Fail { expected: String },
+    Unknown,
+}
+
+// One `day part checksum answer` entry per line; blank lines and
+// lines starting with `#` are ignored. Answers may contain spaces.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    answers: BTreeMap<Key, String>,
+}
+
+impl Manifest {
+    pub fn load(path: &str) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Manifest::parse(&text).map_err(|e| e.in_file(path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(e) => Err(Error::input("a readable answers manifest").found(e.to_string()).in_file(path)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut answers = BTreeMap::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.splitn(4, ' ').collect();
+            let &[day, part, checksum, answer] = &fields[..] else {
+                return Err(Error::input("'day part checksum answer'").on_line(i + 1).found(line));
+            };
+            let bad = |what: &str, found: &str| Error::input(what).on_line(i + 1).found(found);
+
+            let key = Key {
+                day: day.parse().map_err(|_| bad("a day number", day))?,
+                part: match part {
+                    "1" => 1,
+                    "2" => 2,
+                    _ => return Err(bad("part 1 or 2", part)),
+                },
+                checksum: u64::from_str_radix(checksum, 16)
+                    .map_err(|_| bad("a hexadecimal checksum", checksum))?,
+            };
+            answers.insert(key, answer.to_string());
+        }
+
+        Ok(Manifest { answers })
+    }
+
+    pub fn check(&self, key: &Key, answer: &Answer) -> Verdict {
+        match self.answers.get(key) {
+            None => Verdict::Unknown,
+            Some(expected) if *expected == answer.to_string() => Verdict::Pass,
+            Some(expected) => Verdict::Fail { expected: expected.clone() },
+        }
+    }
+
+    pub fn record(&mut self, key: Key, answer: &Answer) {
+        self.answers.insert(key, answer.to_string());
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let mut text = String::from("# day part checksum answer\n");
+        for (key, answer) in &self.answers {
+            text.push_str(&format!("{:02} {} {:016x} {}\n", key.day, key.part, key.checksum, answer));
+        }
+        fs::write(path, text)
+            .map_err(|e| Error::input("a writable answers manifest").found(e.to_string()).in_file(path))
+    }
+}
+
+#[cfg(test)]
+mod manifest_tests {
+    use super::*;
+
+    #[test]
+    fn checksum_is_fnv1a() {
+        assert_eq!(0xcbf29ce484222325, checksum(b""));
+        assert_eq!(0xaf63dc4c8601ec8c, checksum(b"a"));
+    }
+
+    #[test]
+    fn verdicts() {
+        let manifest = Manifest::parse("# comment\n\n01 1 00000000000000ff 7\n01 2 00000000000000ff 5\n").unwrap();
+        let key = |part| Key::new(1, part, 0xff);
+        assert_eq!(Verdict::Pass, manifest.check(&key(Part::One), &Answer::U32(7)));
+        assert_eq!(Verdict::Fail { expected: "5".to_string() },
+            manifest.check(&key(Part::Two), &Answer::U32(6)));
+        assert_eq!(Verdict::Unknown, manifest.check(&Key::new(1, Part::One, 0xfe), &Answer::U32(7)));
+    }
+
+    #[test]
+    fn text_answers_keep_their_spaces() {
+        let manifest = Manifest::parse("13 2 0000000000000001 HELLO WORLD\n").unwrap();
+        let key = Key::new(13, Part::Two, 1);
+        assert_eq!(Verdict::Pass, manifest.check(&key, &Answer::from("HELLO WORLD")));
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let error = Manifest::parse("01 1 00000000000000ff 7\n01 3 00000000000000ff 5\n").unwrap_err();
+        assert_eq!(Some(2), error.line);
+        assert!(Manifest::parse("01 1 7\n").is_err());
+        assert!(Manifest::parse("01 1 nothex 7\n").is_err());
+    }
+
+    #[test]
+    fn manifest_round_trip() {
+        let mut manifest = Manifest::default();
+        manifest.record(Key::new(9, Part::One, 0xabc), &Answer::I64(-4));
+        let path = std::env::temp_dir().join(format!("aoc-answers-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        manifest.save(path).unwrap();
+        assert_eq!(manifest, Manifest::load(path).unwrap());
+        fs::remove_file(path).unwrap();
+    }
+}